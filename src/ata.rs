@@ -0,0 +1,74 @@
+use sha2::{Digest, Sha256};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+
+use crate::keypair::KeypairSchedule;
+use crate::pda::canonical_address;
+use crate::{BatchMatch, PatternSet, SearchStats};
+
+/// Grinds wallet keypairs whose associated token account for `mint` matches,
+/// deriving each account like `get_associated_token_address_with_program_id`.
+pub(crate) struct AtaGrinder {
+    wallets: KeypairSchedule,
+    mint: Pubkey,
+    token_program: Pubkey,
+}
+
+impl AtaGrinder {
+    pub(crate) fn new(base_keypair: &Keypair, mint: Pubkey, token_program: Pubkey) -> Self {
+        Self {
+            wallets: KeypairSchedule::wallets(base_keypair),
+            mint,
+            token_program,
+        }
+    }
+
+    pub(crate) fn wallet(&self, index: u64) -> Keypair {
+        self.wallets.keypair(index)
+    }
+
+    pub(crate) fn mint(&self) -> Pubkey {
+        self.mint
+    }
+
+    pub(crate) fn search_batch(
+        &self,
+        start: u64,
+        count: u64,
+        patterns: &PatternSet,
+        stats: &SearchStats,
+    ) -> Option<BatchMatch> {
+        self.wallets.search_batch_with(start, count, patterns, stats, |wallet| {
+            let seeds_state = Sha256::new()
+                .chain_update(wallet)
+                .chain_update(self.token_program)
+                .chain_update(self.mint);
+            canonical_address(&seeds_state, &spl_associated_token_account::id())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::owner::TOKEN_2022_PROGRAM_ID;
+    use crate::{PatternSpec, Position};
+    use solana_sdk::signature::Signer;
+    use spl_associated_token_account::get_associated_token_address_with_program_id;
+
+    #[test]
+    fn test_ata_search_matches_associated_token_address() {
+        let patterns = PatternSet::new(&[PatternSpec::new("a", Position::End, false).into()]).unwrap();
+        let mint = Pubkey::new_unique();
+        let grinder = AtaGrinder::new(&Keypair::new(), mint, TOKEN_2022_PROGRAM_ID);
+
+        let found = grinder.search_batch(0, 2000, &patterns, &SearchStats::new()).unwrap();
+        let ata = |index| {
+            get_associated_token_address_with_program_id(&grinder.wallet(index).pubkey(), &mint, &TOKEN_2022_PROGRAM_ID)
+        };
+        assert_eq!(ata(found.attempt_index), found.address);
+        for earlier in 0..found.attempt_index {
+            assert!(!ata(earlier).to_string().ends_with('a'));
+        }
+    }
+}
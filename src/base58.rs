@@ -0,0 +1,69 @@
+use crate::pattern::BASE58_ALPHABET;
+
+/// Longest base58 encoding of a 32-byte value.
+pub(crate) const MAX_ENCODED_LEN: usize = 44;
+
+/// 58^4, the largest power of 58 that still fits a u32 limb.
+const CHUNK_DIVISOR: u64 = 58 * 58 * 58 * 58;
+
+/// Encodes a 32-byte value as base58 into `out` without allocating and returns the
+/// encoded part of the buffer. Produces the same output as `Pubkey::to_string`.
+pub(crate) fn encode_32<'a>(bytes: &[u8; 32], out: &'a mut [u8; MAX_ENCODED_LEN]) -> &'a [u8] {
+    let mut words = [0u32; 8];
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+        *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+
+    let mut pos = MAX_ENCODED_LEN;
+    let mut first = words.iter().position(|&w| w != 0).unwrap_or(words.len());
+    while first < words.len() {
+        // Long division of the remaining value by 58^4, yielding four digits per pass
+        let mut rem = 0u64;
+        for word in &mut words[first..] {
+            let cur = (rem << 32) | *word as u64;
+            *word = (cur / CHUNK_DIVISOR) as u32;
+            rem = cur % CHUNK_DIVISOR;
+        }
+        for _ in 0..4 {
+            pos -= 1;
+            out[pos] = BASE58_ALPHABET[(rem % 58) as usize];
+            rem /= 58;
+        }
+        while first < words.len() && words[first] == 0 {
+            first += 1;
+        }
+    }
+
+    // Drop the zero digits of the last pass, then encode leading zero bytes as '1'
+    while pos < MAX_ENCODED_LEN && out[pos] == BASE58_ALPHABET[0] {
+        pos += 1;
+    }
+    for _ in bytes.iter().take_while(|&&b| b == 0) {
+        pos -= 1;
+        out[pos] = BASE58_ALPHABET[0];
+    }
+
+    &out[pos..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn test_matches_pubkey_display() {
+        let mut cases = vec![[0u8; 32], [0xff; 32]];
+        let mut leading_zeros = [0x17u8; 32];
+        leading_zeros[..3].fill(0);
+        cases.push(leading_zeros);
+        cases.extend((0..100).map(|_| Pubkey::new_unique().to_bytes()));
+        cases.extend((0..100).map(|_| rand::random::<[u8; 32]>()));
+
+        let mut out = [0u8; MAX_ENCODED_LEN];
+        for bytes in cases {
+            let encoded = encode_32(&bytes, &mut out);
+            assert_eq!(encoded, Pubkey::new_from_array(bytes).to_string().as_bytes());
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::Result;
+use rand::Rng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use std::time::{Duration, Instant};
+
+use crate::owner::TOKEN_2022_PROGRAM_ID;
+use crate::{measure_rate, CompoundPattern, PatternSpec, Position, SearchMode, CHARSET};
+
+/// Attempts per batch of the legacy loop, small enough to stop close to the deadline.
+const LEGACY_BATCH_SIZE: usize = 1 << 16;
+
+/// CPU throughput of the old allocating search loop next to the current one, and
+/// of both keypair searches.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub threads: usize,
+    /// Attempts/s of the original loop: random `String` seeds, `create_with_seed`, base58 `String`s.
+    pub legacy_rate: f64,
+    /// Attempts/s of the current search loop.
+    pub rate: f64,
+    /// Keys/s of keypair searches, a scalar multiplication per key.
+    pub keypair_rate: f64,
+    /// Keys/s of expanded keypair searches, a point addition per key.
+    pub expanded_keypair_rate: f64,
+}
+
+impl BenchReport {
+    pub fn speedup(&self) -> f64 {
+        self.rate / self.legacy_rate
+    }
+}
+
+/// Measures each search loop for `duration` on a fixed 4-character end pattern.
+pub fn run_benchmark(threads: Option<usize>, duration: Duration) -> Result<BenchReport> {
+    let threads = threads.unwrap_or_else(num_cpus::get);
+    let patterns = [CompoundPattern::from(PatternSpec::new("gems", Position::End, false))];
+
+    let legacy_rate = legacy_rate("gems", "end", threads, duration)?;
+    let rate = measure_rate(&patterns, SearchMode::Seed, Some(threads), duration)?;
+    let keypair_rate = measure_rate(&patterns, SearchMode::Keypair, Some(threads), duration)?;
+    let expanded_keypair_rate = measure_rate(&patterns, SearchMode::ExpandedKeypair, Some(threads), duration)?;
+
+    Ok(BenchReport {
+        threads,
+        legacy_rate,
+        rate,
+        keypair_rate,
+        expanded_keypair_rate,
+    })
+}
+
+/// The search loop as it was before seeds were derived in place, kept as a baseline:
+/// seeds generated on one thread, addresses derived in parallel, then every address
+/// encoded and checked on one thread. Only the batch size differs, so the loop stops
+/// close to the deadline.
+fn legacy_rate(pattern: &str, position: &str, threads: usize, duration: Duration) -> Result<f64> {
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build()?;
+    let base_keypair = Keypair::new();
+    let start_time = Instant::now();
+    let mut attempts = 0u64;
+
+    pool.install(|| {
+        while start_time.elapsed() < duration {
+            let mut seeds = Vec::with_capacity(LEGACY_BATCH_SIZE);
+            let mut addresses = Vec::with_capacity(LEGACY_BATCH_SIZE);
+
+            for _ in 0..LEGACY_BATCH_SIZE {
+                let mut rng = rand::thread_rng();
+                let seed: String = (0..32)
+                    .map(|_| {
+                        let idx = rng.gen_range(0..CHARSET.len());
+                        CHARSET[idx] as char
+                    })
+                    .collect();
+                seeds.push(seed);
+            }
+
+            addresses.par_extend(seeds.par_iter().filter_map(|seed| {
+                Pubkey::create_with_seed(&base_keypair.pubkey(), seed, &TOKEN_2022_PROGRAM_ID)
+                    .ok()
+                    .map(|address| (seed.clone(), address))
+            }));
+            attempts += LEGACY_BATCH_SIZE as u64;
+
+            // Like the baseline, stop checking the batch at a match
+            for (_seed, address) in addresses {
+                if legacy_matches_pattern(&address.to_string(), pattern, false, position) {
+                    break;
+                }
+            }
+        }
+    });
+
+    Ok(attempts as f64 / start_time.elapsed().as_secs_f64())
+}
+
+/// The pattern check of the legacy loop, before patterns were compiled.
+fn legacy_matches_pattern(address: &str, pattern: &str, case_insensitive: bool, position: &str) -> bool {
+    let (address, pattern) = if case_insensitive {
+        (address.to_lowercase(), pattern.to_lowercase())
+    } else {
+        (address.to_string(), pattern.to_string())
+    };
+
+    match position {
+        "start" => address.starts_with(&pattern),
+        "end" => address.ends_with(&pattern),
+        _ => false,
+    }
+}
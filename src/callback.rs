@@ -0,0 +1,38 @@
+use std::{
+    fmt,
+    sync::{mpsc::Sender, Arc},
+};
+
+/// A caller-supplied function the search invokes from its own threads.
+pub struct Callback<T>(Arc<dyn Fn(&T) + Send + Sync>);
+
+impl<T> Callback<T> {
+    pub fn new(callback: impl Fn(&T) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+
+    pub(crate) fn call(&self, value: &T) {
+        (self.0)(value)
+    }
+}
+
+impl<T> Clone for Callback<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+/// Forwards values into a channel. Values are dropped once the receiver is gone.
+impl<T: Clone + Send + 'static> From<Sender<T>> for Callback<T> {
+    fn from(sender: Sender<T>) -> Self {
+        Self::new(move |value: &T| {
+            let _ = sender.send(value.clone());
+        })
+    }
+}
+
+impl<T> fmt::Debug for Callback<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Callback")
+    }
+}
@@ -0,0 +1,308 @@
+use anyhow::{anyhow, Context, Result};
+use aes_gcm_siv::{
+    aead::{Aead, NewAead},
+    Aes256GcmSiv, Key, Nonce,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use crate::seed::{SeedFormat, SeedSchedule};
+use crate::{CompoundPattern, OwnerProgram, PdaSeeds, SearchBase, SearchMode, SearchOptions};
+
+const CHECKPOINT_VERSION: u32 = 1;
+
+/// PBKDF2-HMAC-SHA256 rounds used to turn the passphrase into an encryption key.
+const KDF_ROUNDS: u32 = 600_000;
+
+/// Where and how often a search saves its progress.
+#[derive(Clone)]
+pub struct CheckpointOptions {
+    pub path: PathBuf,
+    /// Encrypts the base keypair stored in the checkpoint.
+    pub passphrase: String,
+    pub interval: Duration,
+}
+
+impl fmt::Debug for CheckpointOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CheckpointOptions")
+            .field("path", &self.path)
+            .field("passphrase", &"<redacted>")
+            .field("interval", &self.interval)
+            .finish()
+    }
+}
+
+/// Everything needed to continue a search: its patterns, the encrypted base
+/// keypair and how far the seed schedule got.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub version: u32,
+    pub mode: SearchMode,
+    pub patterns: Vec<CompoundPattern>,
+    pub base_pubkey: String,
+    /// AES-256-GCM-SIV ciphertext of the base keypair bytes, hex encoded. `None`
+    /// for pubkey-only searches, which have no secret to store.
+    pub encrypted_base_keypair: Option<String>,
+    pub nonce: String,
+    pub kdf_salt: String,
+    pub kdf_rounds: u32,
+    pub seed_format: SeedFormat,
+    pub owners: Vec<OwnerProgram>,
+    /// Program and fixed seeds of a PDA search.
+    pub pda: Option<PdaSeeds>,
+    /// Mint of an ATA search.
+    pub ata_mint: Option<Pubkey>,
+    /// Whether the search kept track of the candidate that came closest.
+    pub track_best: bool,
+    pub seed_salt: String,
+    /// Attempt index to continue from. Every lower index has been checked.
+    pub next_index: u64,
+    /// Matches the search still had to find, those before `next_index` excluded.
+    pub match_count: usize,
+    pub attempts: u64,
+    pub elapsed_secs: f64,
+}
+
+impl Checkpoint {
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read checkpoint {}", path.display()))?;
+        let checkpoint: Self = serde_json::from_str(&json)
+            .with_context(|| format!("Invalid checkpoint {}", path.display()))?;
+        if checkpoint.version != CHECKPOINT_VERSION {
+            return Err(anyhow!("Unsupported checkpoint version {}", checkpoint.version));
+        }
+        Ok(checkpoint)
+    }
+
+    pub fn decrypt_base_keypair(&self, passphrase: &str) -> Result<Keypair> {
+        let ciphertext = self
+            .encrypted_base_keypair
+            .as_ref()
+            .ok_or_else(|| anyhow!("Checkpoint of a pubkey-only search holds no base keypair"))?;
+        let key = derive_key(passphrase, &hex::decode(&self.kdf_salt)?, self.kdf_rounds);
+        let nonce: [u8; 12] = hex::decode(&self.nonce)?
+            .try_into()
+            .map_err(|_| anyhow!("Invalid checkpoint nonce"))?;
+        let bytes = Aes256GcmSiv::new(&Key::from(key))
+            .decrypt(
+                &Nonce::from(nonce),
+                hex::decode(ciphertext)?.as_slice(),
+            )
+            .map_err(|_| anyhow!("Wrong passphrase or corrupted checkpoint"))?;
+
+        let keypair = Keypair::from_bytes(&bytes)?;
+        if keypair.pubkey().to_string() != self.base_pubkey {
+            return Err(anyhow!("Checkpoint base keypair doesn't match its pubkey"));
+        }
+        Ok(keypair)
+    }
+
+    pub(crate) fn seed_schedule(&self) -> Result<SeedSchedule> {
+        SeedSchedule::from_salt(&self.seed_format, &self.seed_salt)
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], rounds: u32) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, rounds, &mut key);
+    key
+}
+
+/// Saves the checkpoints of one search. The base keypair is encrypted once up
+/// front, later saves only update the progress fields.
+pub(crate) struct CheckpointWriter {
+    path: PathBuf,
+    interval: Duration,
+    checkpoint: Checkpoint,
+    last_save: Instant,
+}
+
+impl CheckpointWriter {
+    /// Takes the mode, seed format, owners, PDA seeds, ATA mint and whether to track
+    /// the closest candidate from `search`.
+    pub(crate) fn new(
+        options: &CheckpointOptions,
+        search: &SearchOptions,
+        patterns: &[CompoundPattern],
+        base: &SearchBase,
+        schedule: &SeedSchedule,
+    ) -> Result<Self> {
+        Self::with_kdf_rounds(options, search, patterns, base, schedule, KDF_ROUNDS)
+    }
+
+    fn with_kdf_rounds(
+        options: &CheckpointOptions,
+        search: &SearchOptions,
+        patterns: &[CompoundPattern],
+        base: &SearchBase,
+        schedule: &SeedSchedule,
+        kdf_rounds: u32,
+    ) -> Result<Self> {
+        let mut kdf_salt = [0u8; 16];
+        let mut nonce = [0u8; 12];
+        rand::thread_rng().fill_bytes(&mut kdf_salt);
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let encrypted_base_keypair = base
+            .keypair()
+            .map(|keypair| {
+                let key = derive_key(&options.passphrase, &kdf_salt, kdf_rounds);
+                Aes256GcmSiv::new(&Key::from(key))
+                    .encrypt(&Nonce::from(nonce), keypair.to_bytes().as_slice())
+                    .map(hex::encode)
+                    .map_err(|_| anyhow!("Failed to encrypt base keypair"))
+            })
+            .transpose()?;
+
+        let checkpoint = Checkpoint {
+            version: CHECKPOINT_VERSION,
+            mode: search.mode,
+            patterns: patterns.to_vec(),
+            base_pubkey: base.pubkey().to_string(),
+            encrypted_base_keypair,
+            nonce: hex::encode(nonce),
+            kdf_salt: hex::encode(kdf_salt),
+            kdf_rounds,
+            seed_format: search.seed_format.clone(),
+            owners: search.owner_programs(),
+            pda: search.pda.clone(),
+            ata_mint: search.ata_mint,
+            track_best: search.track_best,
+            seed_salt: schedule.salt().to_string(),
+            next_index: 0,
+            match_count: 1,
+            attempts: 0,
+            elapsed_secs: 0.0,
+        };
+        Ok(Self::resume(options, checkpoint))
+    }
+
+    /// Keeps saving into a checkpoint that was loaded to resume a search.
+    pub(crate) fn resume(options: &CheckpointOptions, checkpoint: Checkpoint) -> Self {
+        Self {
+            path: options.path.clone(),
+            interval: options.interval,
+            checkpoint,
+            last_save: Instant::now(),
+        }
+    }
+
+    pub(crate) fn due(&self) -> bool {
+        self.last_save.elapsed() >= self.interval
+    }
+
+    pub(crate) fn save(&mut self, next_index: u64, match_count: usize, attempts: u64, elapsed_secs: f64) -> Result<()> {
+        self.checkpoint.next_index = next_index;
+        self.checkpoint.match_count = match_count;
+        self.checkpoint.attempts = attempts;
+        self.checkpoint.elapsed_secs = elapsed_secs;
+
+        // Write next to the checkpoint and rename, so a crash never leaves half a file.
+        // Appended rather than swapped in, any extension could be the checkpoint's own.
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".partial");
+        let tmp = PathBuf::from(tmp);
+        std::fs::write(&tmp, serde_json::to_string_pretty(&self.checkpoint)?)
+            .with_context(|| format!("Failed to write checkpoint {}", tmp.display()))?;
+        std::fs::rename(&tmp, &self.path)
+            .with_context(|| format!("Failed to write checkpoint {}", self.path.display()))?;
+
+        self.last_save = Instant::now();
+        Ok(())
+    }
+
+    /// Deletes the checkpoint of a search that has nothing left to resume, along
+    /// with the encrypted base keypair it holds.
+    pub(crate) fn remove(self) -> Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("Failed to remove checkpoint {}", self.path.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PatternSpec, Position};
+    use solana_program::pubkey::Pubkey;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        let path = std::env::temp_dir().join(format!("token22-vanity-{}.json", rand::random::<u64>()));
+        let options = CheckpointOptions {
+            path: path.clone(),
+            passphrase: "hunter2".to_string(),
+            interval: Duration::from_secs(60),
+        };
+        let patterns = [PatternSpec::new("gems", Position::End, true).into()];
+        let base_keypair = Arc::new(Keypair::new());
+        let search = SearchOptions {
+            seed_format: SeedFormat {
+                len: 16,
+                prefix: "gems-".to_string(),
+                ..SeedFormat::default()
+            },
+            owners: vec![OwnerProgram::SplToken, OwnerProgram::Token2022],
+            track_best: true,
+            ..SearchOptions::default()
+        };
+        let schedule = SeedSchedule::random(&search.seed_format).unwrap();
+
+        let mut writer = CheckpointWriter::with_kdf_rounds(
+            &options,
+            &search,
+            &patterns,
+            &SearchBase::from_keypair(base_keypair.clone()),
+            &schedule,
+            1_000,
+        )
+        .unwrap();
+        writer.save(42, 3, 40, 1.5).unwrap();
+
+        let checkpoint = Checkpoint::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(checkpoint.patterns, patterns);
+        assert_eq!((checkpoint.next_index, checkpoint.match_count, checkpoint.attempts), (42, 3, 40));
+        assert_eq!(checkpoint.owners, [OwnerProgram::SplToken, OwnerProgram::Token2022]);
+        assert!(checkpoint.track_best);
+        assert_eq!(checkpoint.seed_schedule().unwrap().cursor(9).to_string(), schedule.cursor(9).to_string());
+        assert_eq!(
+            checkpoint.decrypt_base_keypair("hunter2").unwrap().to_bytes(),
+            base_keypair.to_bytes()
+        );
+        assert!(checkpoint.decrypt_base_keypair("hunter3").is_err());
+
+        // Pubkey-only searches store no keypair at all
+        let pubkey = Pubkey::new_unique();
+        let mut writer = CheckpointWriter::with_kdf_rounds(
+            &options,
+            &search,
+            &patterns,
+            &SearchBase::from_pubkey(pubkey),
+            &schedule,
+            1_000,
+        )
+        .unwrap();
+        writer.save(0, 1, 0, 0.0).unwrap();
+
+        let checkpoint = Checkpoint::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(checkpoint.base_pubkey, pubkey.to_string());
+        assert!(checkpoint.encrypted_base_keypair.is_none());
+        assert!(checkpoint.decrypt_base_keypair("hunter2").is_err());
+    }
+}
@@ -55,12 +55,13 @@ async function findVanityAddress(config: TokenConfig): Promise<{
     seed: string;
     keypairJson: string;
 }> {
-    // For end patterns, use our Rust implementation for better performance
-    if (config.position === 'end') {
-        console.log('Using optimized Rust implementation for end pattern search...');
+    // End patterns grind create_with_seed addresses, start patterns mint keypairs
+    if (config.position === 'end' || config.position === 'start') {
+        console.log(`Using optimized Rust implementation for ${config.position} pattern search...`);
         const args = [
             '--pattern', config.pattern,
-            '--position', 'end',
+            '--position', config.position,
+            ...(config.position === 'start' ? ['--mode', 'keypair'] : []),
             config.useGpu ? '--gpu' : '',
             config.caseInsensitive ? '--case-insensitive' : '',
             config.threads ? `--threads ${config.threads}` : ''
@@ -142,45 +143,9 @@ async function findVanityAddress(config: TokenConfig): Promise<{
                 reject(new Error(`Failed to start Rust process: ${error.message}`));
             });
         });
-    } else {
-        // For start patterns, use the existing Solana CLI (it's fast enough for start patterns)
-        console.log('Using Solana CLI for start pattern search...');
-        const result = execSync(`solana-keygen grind --starts-with ${config.pattern}:1${config.caseInsensitive ? ' --ignore-case' : ''}`, {
-            encoding: 'utf8'
-        });
-
-        // Parse the output
-        const lines = result.split('\n');
-        const keypairLine = lines.find(l => l.includes('Wrote keypair to'));
-        if (!keypairLine) {
-            throw new Error('Could not find keypair in output');
-        }
-
-        // Extract the filename
-        const keypairFile = keypairLine.split('Wrote keypair to ')[1].trim();
-        
-        // Read the generated keypair file
-        const keypairJson = fs.readFileSync(keypairFile, 'utf8');
-        
-        // Create keypair to verify it matches the pattern
-        const keypair = Keypair.fromSecretKey(new Uint8Array(JSON.parse(keypairJson)));
-        const pubkey = keypair.publicKey.toBase58();
-        
-        if (!pubkey.toLowerCase().startsWith(config.pattern.toLowerCase())) {
-            fs.unlinkSync(keypairFile);
-            throw new Error(`Generated address ${pubkey} does not start with ${config.pattern}`);
-        }
-        
-        // Move the keypair file to our token_keys directory
-        fs.renameSync(keypairFile, 'token_keys/token_keypair.json');
-
-        // For start patterns, use the keypair's public key directly
-        return {
-            tokenAddress: pubkey,
-            seed: '', // No seed needed for start patterns
-            keypairJson
-        };
     }
+
+    throw new Error(`Unsupported pattern position: ${config.position}`);
 }
 
 async function fundKeypair(keypairPath: string): Promise<void> {
@@ -405,8 +370,11 @@ async function createToken(config: TokenConfig): Promise<void> {
     });
     fs.writeFileSync(seedFile, JSON.stringify(seedData, null, 2));
 
-    // Fund the keypair
-    await fundKeypair(keypairPath);
+    // Fund the keypair. A start pattern keypair is the mint itself, which can't pay
+    // fees once it is created, so the admin wallet pays for it instead
+    if (config.position === 'end') {
+        await fundKeypair(keypairPath);
+    }
 
     // Create token with seed
     console.log('Initializing token...');
@@ -422,6 +390,8 @@ async function createToken(config: TokenConfig): Promise<void> {
     // Get admin keypair for authorities
     const adminKeypairPath = getAdminKeypairPath();
     const adminKeypair = Keypair.fromSecretKey(new Uint8Array(JSON.parse(fs.readFileSync(adminKeypairPath, 'utf8'))));
+    const signers = config.position === 'end' ? [keypair] : [adminKeypair, keypair];
+    const feePayerPath = config.position === 'end' ? keypairPath : adminKeypairPath;
     
     if (config.position === 'end') {
         // Add instruction to create account with seed for end patterns
@@ -440,7 +410,7 @@ async function createToken(config: TokenConfig): Promise<void> {
         // For start patterns, create account directly using the keypair
         transaction.add(
             SystemProgram.createAccount({
-                fromPubkey: keypair.publicKey,
+                fromPubkey: adminKeypair.publicKey,
                 newAccountPubkey: tokenAddress,
                 lamports: rentExemptBalance,
                 space: space,
@@ -472,7 +442,7 @@ async function createToken(config: TokenConfig): Promise<void> {
 
     // Send and confirm transaction
     console.log('Sending transaction...');
-    const signature = await connection.sendTransaction(transaction, [keypair]);
+    const signature = await connection.sendTransaction(transaction, signers);
     await connection.confirmTransaction(signature);
     
     console.log(`Token created with signature: ${signature}`);
@@ -491,12 +461,12 @@ async function createToken(config: TokenConfig): Promise<void> {
 
     // Transfer mint authority to admin wallet
     console.log('Step 1: Transferring Mint Authority...');
-    execSync(`spl-token authorize ${tokenAddress.toBase58()} mint ${adminKeypair.publicKey.toBase58()} --program-id TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb --fee-payer "${keypairPath}" --owner "${keypairPath}" --url mainnet-beta`);
+    execSync(`spl-token authorize ${tokenAddress.toBase58()} mint ${adminKeypair.publicKey.toBase58()} --program-id TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb --fee-payer "${feePayerPath}" --owner "${keypairPath}" --url mainnet-beta`);
     console.log('✓ Mint authority transferred successfully');
 
     // Transfer freeze authority to admin wallet
     console.log('\nStep 2: Transferring Freeze Authority...');
-    execSync(`spl-token authorize ${tokenAddress.toBase58()} freeze ${adminKeypair.publicKey.toBase58()} --program-id TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb --fee-payer "${keypairPath}" --owner "${keypairPath}" --url mainnet-beta`);
+    execSync(`spl-token authorize ${tokenAddress.toBase58()} freeze ${adminKeypair.publicKey.toBase58()} --program-id TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb --fee-payer "${feePayerPath}" --owner "${keypairPath}" --url mainnet-beta`);
     console.log('✓ Freeze authority transferred successfully');
 
     // Verify authorities were transferred correctly
@@ -775,7 +745,7 @@ program
     .command('create')
     .description('Create a new token with a vanity address')
     .requiredOption('-p, --pattern <pattern>', 'pattern to search for')
-    .requiredOption('--position <position>', 'pattern position: "start" (coming soon) or "end"')
+    .requiredOption('--position <position>', 'pattern position: "start" or "end"')
     .requiredOption('-n, --name <name>', 'token name')
     .requiredOption('-s, --symbol <symbol>', 'token symbol')
     .requiredOption('-d, --description <description>', 'token description')
@@ -785,13 +755,6 @@ program
     .option('-t, --threads <number>', 'number of CPU threads to use for end pattern search')
     .option('--case-insensitive', 'case insensitive pattern matching')
     .action((options) => {
-        // Exit immediately for start pattern with just the message
-        if (options.position === 'start') {
-            console.log('\n🚧 Start pattern matching is coming soon! 🚧');
-            console.log('Please use --position end for now.\n');
-            process.exit(0);
-        }
-
         // Switch to mainnet immediately
         execSync('solana config set --url mainnet-beta', { stdio: 'inherit' });
         console.log('\nSwitched to Solana Mainnet');
@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+
+use crate::pattern::{
+    CharClass, CompiledPattern, CompoundPattern, PatternError, PatternSet, Position,
+    ADDRESS_LEN, BASE58_ALPHABET,
+};
+
+/// How likely a single candidate address is to match a search.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Difficulty {
+    /// Probability that one attempt produces a match.
+    pub probability: f64,
+}
+
+impl Difficulty {
+    pub(crate) fn of(patterns: &PatternSet) -> Self {
+        // 1 - prod(1 - p), computed in log space so tiny probabilities survive
+        let probability = -patterns
+            .compiled()
+            .iter()
+            .map(|parts| (-parts.iter().map(part_probability).product::<f64>()).ln_1p())
+            .sum::<f64>()
+            .exp_m1();
+
+        Self { probability }
+    }
+
+    /// Expected number of attempts until the first match.
+    pub fn expected_attempts(&self) -> f64 {
+        1.0 / self.probability
+    }
+
+    /// Probability of having found at least one match after `attempts` attempts.
+    pub fn success_probability(&self, attempts: u64) -> f64 {
+        -(attempts as f64 * (-self.probability).ln_1p()).exp_m1()
+    }
+
+    /// Number of attempts needed to find a match with the given probability.
+    pub fn attempts_for_probability(&self, probability: f64) -> f64 {
+        (-probability).ln_1p() / (-self.probability).ln_1p()
+    }
+}
+
+/// Estimates the difficulty of finding an address matching any of `patterns`.
+pub fn estimate_difficulty(patterns: &[CompoundPattern]) -> Result<Difficulty, PatternError> {
+    Ok(PatternSet::new(patterns)?.difficulty())
+}
+
+/// Probability of a single pattern holding. Characters are treated as uniformly
+/// distributed, except for the first one: a 32-byte value only spans part of the
+/// 44-digit base58 range, so some leading characters are far more common than others.
+fn part_probability(part: &CompiledPattern) -> f64 {
+    let uniform = |classes: &[CharClass]| -> f64 {
+        classes
+            .iter()
+            .map(|class| class.base58_count() as f64 / BASE58_ALPHABET.len() as f64)
+            .product()
+    };
+
+    match part.position {
+        Position::Start | Position::At(0) => {
+            let leading = leading_char_probabilities();
+            let first: f64 = BASE58_ALPHABET
+                .iter()
+                .zip(leading.iter())
+                .filter(|(&c, _)| part.classes[0].contains(c))
+                .map(|(_, p)| p)
+                .sum();
+            first * uniform(&part.classes[1..])
+        }
+        Position::Contains => {
+            let windows = ADDRESS_LEN.saturating_sub(part.classes.len()) + 1;
+            (uniform(&part.classes) * windows as f64).min(1.0)
+        }
+        Position::End | Position::At(_) => uniform(&part.classes),
+    }
+}
+
+/// Probability of each base58 digit being the first character of a uniformly
+/// random 32-byte address.
+fn leading_char_probabilities() -> [f64; 58] {
+    let total = 2f64.powi(256);
+    // Values below 2^248 start with a zero byte, which base58 encodes as '1'
+    let no_zero_byte = 2f64.powi(248);
+    let mut probabilities = [0.0; 58];
+    probabilities[0] = no_zero_byte / total;
+
+    for len in 1..=ADDRESS_LEN as i32 {
+        let digit_span = 58f64.powi(len - 1);
+        let lo = digit_span.max(no_zero_byte);
+        let hi = (digit_span * 58.0).min(total);
+        if lo >= hi {
+            continue;
+        }
+        for (digit, probability) in probabilities.iter_mut().enumerate().skip(1) {
+            let from = (digit as f64 * digit_span).max(lo);
+            let to = ((digit + 1) as f64 * digit_span).min(hi);
+            if to > from {
+                *probability += (to - from) / total;
+            }
+        }
+    }
+
+    probabilities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PatternSpec;
+
+    fn estimate(pattern: &str, position: Position) -> Difficulty {
+        estimate_difficulty(&[PatternSpec::new(pattern, position, false).into()]).unwrap()
+    }
+
+    #[test]
+    fn test_leading_characters_are_biased() {
+        let leading = leading_char_probabilities();
+        assert!((leading.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+
+        // Full 44-character addresses only start with '2'..'J'
+        assert!(estimate("5", Position::Start).probability > 0.05);
+        assert!(estimate("z", Position::Start).probability < 0.002);
+        assert!((estimate("z", Position::End).probability - 1.0 / 58.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_combined_probabilities() {
+        let start = estimate("Tx", Position::Start);
+        let end = estimate("gems", Position::End);
+        let both = estimate_difficulty(&[CompoundPattern::prefix_suffix(
+            PatternSpec::new("Tx", Position::Start, false),
+            PatternSpec::new("gems", Position::End, false),
+        )])
+        .unwrap();
+        assert!((both.probability - start.probability * end.probability).abs() < both.probability * 1e-9);
+
+        let either = estimate_difficulty(&[
+            PatternSpec::new("gems", Position::End, false).into(),
+            PatternSpec::new("GEMS", Position::End, false).into(),
+        ])
+        .unwrap();
+        assert!((either.expected_attempts() - end.expected_attempts() / 2.0).abs() < end.expected_attempts() * 1e-6);
+
+        let half = end.attempts_for_probability(0.5);
+        assert!((end.success_probability(half as u64) - 0.5).abs() < 1e-6);
+    }
+}
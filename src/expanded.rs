@@ -0,0 +1,282 @@
+use anyhow::{anyhow, Result};
+use curve25519_dalek::{
+    constants::{ED25519_BASEPOINT_COMPRESSED, ED25519_BASEPOINT_TABLE},
+    scalar::Scalar,
+};
+use ed25519_dalek::{ExpandedSecretKey, PublicKey};
+use rayon::prelude::*;
+use sha2::{Digest, Sha256, Sha512};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer, SignerError};
+use std::fmt;
+
+use crate::base58::MAX_ENCODED_LEN;
+use crate::field::{FieldElement, EDWARDS_D};
+use crate::{BatchMatch, ChunkBest, PatternSet, SearchStats, CHUNK_SIZE};
+
+/// Points normalized with one field inversion.
+const NORMALIZE_BATCH: usize = 256;
+
+/// Keep the scalars and nonce keys unrelated to the other keys of the same secret.
+const SCALAR_TAG: &[u8] = b"token22-vanity expanded key scalar";
+const NONCE_TAG: &[u8] = b"token22-vanity expanded key nonce";
+
+/// Length of `ExpandedKeypair::to_bytes`: scalar, nonce key and public key.
+pub const EXPANDED_KEYPAIR_LENGTH: usize = 96;
+
+/// An ed25519 key given by its scalar rather than by a seed, as found by expanded
+/// keypair searches. No seed hashes to such a scalar, so the Solana CLI and web3.js
+/// can't load it, but it signs transactions through `Signer` like any keypair.
+pub struct ExpandedKeypair {
+    secret: ExpandedSecretKey,
+    public: PublicKey,
+}
+
+impl ExpandedKeypair {
+    fn new(scalar: Scalar, nonce: [u8; 32]) -> Self {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(scalar.as_bytes());
+        bytes[32..].copy_from_slice(&nonce);
+        Self {
+            secret: ExpandedSecretKey::from_bytes(&bytes).expect("64 bytes"),
+            // `PublicKey::from(&ExpandedSecretKey)` clamps the scalar first, which
+            // would give the key of another scalar than the one signing
+            public: PublicKey::from_bytes((&scalar * &ED25519_BASEPOINT_TABLE).compress().as_bytes())
+                .expect("multiples of the base point are public keys"),
+        }
+    }
+
+    /// Reads the bytes of `to_bytes`, checking the public key against the scalar.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != EXPANDED_KEYPAIR_LENGTH {
+            return Err(anyhow!("Expanded keypairs are {} bytes, got {}", EXPANDED_KEYPAIR_LENGTH, bytes.len()));
+        }
+        let scalar = Scalar::from_canonical_bytes(bytes[..32].try_into().expect("32 bytes"))
+            .ok_or_else(|| anyhow!("Expanded keypair scalar isn't reduced"))?;
+        let keypair = Self::new(scalar, bytes[32..64].try_into().expect("32 bytes"));
+        if keypair.public.as_bytes()[..] != bytes[64..] {
+            return Err(anyhow!("Expanded keypair doesn't match its public key"));
+        }
+        Ok(keypair)
+    }
+
+    pub fn to_bytes(&self) -> [u8; EXPANDED_KEYPAIR_LENGTH] {
+        let mut bytes = [0u8; EXPANDED_KEYPAIR_LENGTH];
+        bytes[..64].copy_from_slice(&self.secret.to_bytes());
+        bytes[64..].copy_from_slice(self.public.as_bytes());
+        bytes
+    }
+}
+
+impl Signer for ExpandedKeypair {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(Pubkey::new_from_array(self.public.to_bytes()))
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        Ok(Signature::from(self.secret.sign(message, &self.public).to_bytes()))
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+impl fmt::Debug for ExpandedKeypair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ExpandedKeypair({})", self.pubkey())
+    }
+}
+
+/// A point in extended coordinates, x = X/Z, y = Y/Z and xy = T/Z.
+#[derive(Clone, Copy)]
+struct ExtendedPoint {
+    x: FieldElement,
+    y: FieldElement,
+    z: FieldElement,
+    t: FieldElement,
+}
+
+/// An affine point prepared for mixed addition: y + x, y - x and 2dxy.
+struct NielsPoint {
+    y_plus_x: FieldElement,
+    y_minus_x: FieldElement,
+    xy2d: FieldElement,
+}
+
+impl ExtendedPoint {
+    fn from_affine(x: FieldElement, y: FieldElement) -> Self {
+        Self {
+            x,
+            y,
+            z: FieldElement::ONE,
+            t: x * y,
+        }
+    }
+
+    fn to_niels(self) -> NielsPoint {
+        // Only ever called on affine points, Z = 1
+        NielsPoint {
+            y_plus_x: self.y + self.x,
+            y_minus_x: self.y - self.x,
+            xy2d: self.t * EDWARDS_D + self.t * EDWARDS_D,
+        }
+    }
+
+    /// Mixed addition for a = -1 twisted Edwards curves, 7 multiplications.
+    fn add(&self, other: &NielsPoint) -> Self {
+        let a = (self.y - self.x) * other.y_minus_x;
+        let b = (self.y + self.x) * other.y_plus_x;
+        let c = self.t * other.xy2d;
+        let d = self.z + self.z;
+        let (e, f, g, h) = (b - a, d - c, d + c, b + a);
+        Self {
+            x: e * f,
+            y: g * h,
+            z: f * g,
+            t: e * h,
+        }
+    }
+}
+
+/// Maps attempt indices to expanded keypairs: index i has the scalar s + i for a
+/// start scalar s derived from the secret key of the search's base keypair. Its
+/// public key is then the previous one plus the base point, so successive
+/// candidates cost a point addition instead of a scalar multiplication.
+#[derive(Clone)]
+pub(crate) struct ExpandedKeypairSchedule {
+    start: Scalar,
+    /// Tag and base secret key, hashed once.
+    nonce_state: Sha256,
+}
+
+impl ExpandedKeypairSchedule {
+    pub(crate) fn new(base_keypair: &Keypair) -> Self {
+        let secret = &base_keypair.to_bytes()[..32];
+        let wide: [u8; 64] = Sha512::new().chain_update(SCALAR_TAG).chain_update(secret).finalize().into();
+        Self {
+            start: Scalar::from_bytes_mod_order_wide(&wide),
+            nonce_state: Sha256::new().chain_update(NONCE_TAG).chain_update(secret),
+        }
+    }
+
+    pub(crate) fn keypair(&self, index: u64) -> ExpandedKeypair {
+        // Keys one step apart never share a nonce key, which could leak their scalars
+        let nonce = self.nonce_state.clone().chain_update(index.to_le_bytes()).finalize().into();
+        ExpandedKeypair::new(self.start + Scalar::from(index), nonce)
+    }
+
+    fn point(&self, index: u64) -> ExtendedPoint {
+        let compressed = (&(self.start + Scalar::from(index)) * &ED25519_BASEPOINT_TABLE).compress();
+        let (x, y) = FieldElement::decompress(compressed.as_bytes()).expect("multiples of the base point decompress");
+        ExtendedPoint::from_affine(x, y)
+    }
+
+    /// Grinds the keys of `start..start + count`. Each chunk starts with one scalar
+    /// multiplication and adds the base point from there, normalizing
+    /// `NORMALIZE_BATCH` points with a single inversion. The lowest matching index
+    /// wins and ends the batch.
+    pub(crate) fn search_batch(
+        &self,
+        start: u64,
+        count: u64,
+        patterns: &PatternSet,
+        stats: &SearchStats,
+    ) -> Option<BatchMatch> {
+        let (base_x, base_y) =
+            FieldElement::decompress(ED25519_BASEPOINT_COMPRESSED.as_bytes()).expect("the base point decompresses");
+        let step = ExtendedPoint::from_affine(base_x, base_y).to_niels();
+
+        let result = (0..count.div_ceil(CHUNK_SIZE as u64))
+            .into_par_iter()
+            .find_map_first(|chunk| {
+                let chunk_start = start + chunk * CHUNK_SIZE as u64;
+                let chunk_end = (chunk_start + CHUNK_SIZE as u64).min(start + count);
+                let mut point = self.point(chunk_start);
+                let mut points = Vec::with_capacity(NORMALIZE_BATCH);
+                let mut inverses = Vec::with_capacity(NORMALIZE_BATCH);
+                let mut scratch = Vec::with_capacity(NORMALIZE_BATCH);
+                let mut encoded = [0u8; MAX_ENCODED_LEN];
+                let mut best = ChunkBest::new(stats);
+
+                let mut batch_start = chunk_start;
+                while batch_start < chunk_end {
+                    let batch_len = (NORMALIZE_BATCH as u64).min(chunk_end - batch_start) as usize;
+                    points.clear();
+                    for _ in 0..batch_len {
+                        points.push(point);
+                        point = point.add(&step);
+                    }
+                    inverses.clear();
+                    inverses.extend(points.iter().map(|point| point.z));
+                    FieldElement::batch_invert(&mut inverses, &mut scratch);
+
+                    for (offset, (point, z_inverse)) in points.iter().zip(&inverses).enumerate() {
+                        let mut address = (point.y * *z_inverse).to_bytes();
+                        address[31] |= ((point.x * *z_inverse).is_negative() as u8) << 7;
+                        let index = batch_start + offset as u64;
+
+                        if let Some(pattern) = patterns.find_match_address(&address, &mut encoded) {
+                            return Some(BatchMatch {
+                                attempt_index: index,
+                                owner: 0,
+                                address: Pubkey::new_from_array(address),
+                                pattern,
+                            });
+                        }
+
+                        best.offer(patterns, &address, index, 0);
+                    }
+                    batch_start += batch_len as u64;
+                }
+
+                best.finish();
+                None
+            });
+
+        let attempts = result.map_or(count, |found| found.attempt_index - start + 1);
+        stats.attempts.fetch_add(attempts, std::sync::atomic::Ordering::Relaxed);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PatternSpec, Position};
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn test_incremental_keys_match_scalar_multiplication() {
+        let patterns = PatternSet::new(&[PatternSpec::new("zzzzzzzz", Position::End, false).into()]).unwrap();
+        let schedule = ExpandedKeypairSchedule::new(&Keypair::new());
+        let stats = SearchStats::tracking_best();
+
+        // Spans a chunk boundary and a partial normalization batch
+        let start = CHUNK_SIZE as u64 - 300;
+        assert!(schedule.search_batch(start, 1000, &patterns, &stats).is_none());
+        assert_eq!(stats.attempts.load(Ordering::Relaxed), 1000);
+        let best = stats.best().unwrap();
+        assert_eq!(schedule.keypair(best.attempt_index).pubkey(), best.address);
+
+        let patterns = PatternSet::new(&[PatternSpec::new("a", Position::End, false).into()]).unwrap();
+        let found = schedule.search_batch(start, 1000, &patterns, &SearchStats::new()).unwrap();
+        assert_eq!(schedule.keypair(found.attempt_index).pubkey(), found.address);
+        for earlier in start..found.attempt_index {
+            assert!(!schedule.keypair(earlier).pubkey().to_string().ends_with('a'));
+        }
+    }
+
+    #[test]
+    fn test_expanded_keypair_signs_and_round_trips() {
+        let keypair = ExpandedKeypairSchedule::new(&Keypair::new()).keypair(42);
+        let signature = keypair.sign_message(b"create mint");
+        assert!(signature.verify(keypair.pubkey().as_ref(), b"create mint"));
+
+        let bytes = keypair.to_bytes();
+        assert_eq!(ExpandedKeypair::from_bytes(&bytes).unwrap().pubkey(), keypair.pubkey());
+        let mut tampered = bytes;
+        tampered[70] ^= 1;
+        assert!(ExpandedKeypair::from_bytes(&tampered).is_err());
+    }
+}
@@ -0,0 +1,249 @@
+//! Arithmetic modulo 2^255 - 19 in five 51-bit limbs, just enough to add ed25519
+//! points and batch their normalization. curve25519-dalek keeps its field private
+//! and only normalizes one point per inversion.
+
+use std::ops::{Add, Mul, Neg, Sub};
+
+const MASK: u64 = (1 << 51) - 1;
+
+/// Little-endian exponents for `pow`.
+const P_MINUS_2: [u8; 32] = exponent(0xeb, 0x7f);
+const P_MINUS_5_OVER_8: [u8; 32] = exponent(0xfd, 0x0f);
+
+/// 0xff bytes between the given first and last byte.
+const fn exponent(first: u8, last: u8) -> [u8; 32] {
+    let mut bytes = [0xff; 32];
+    bytes[0] = first;
+    bytes[31] = last;
+    bytes
+}
+
+/// The edwards25519 curve constant d = -121665/121666.
+pub(crate) const EDWARDS_D: FieldElement = FieldElement([
+    929955233495203,
+    466365720129213,
+    1662059464998953,
+    2033849074728123,
+    1442794654840575,
+]);
+
+const SQRT_M1: FieldElement = FieldElement([
+    1718705420411056,
+    234908883556509,
+    2233514472574048,
+    2117202627021982,
+    765476049583133,
+]);
+
+/// Limbs stay below 2^52 between operations.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FieldElement([u64; 5]);
+
+impl FieldElement {
+    pub(crate) const ZERO: Self = Self([0; 5]);
+    pub(crate) const ONE: Self = Self([1, 0, 0, 0, 0]);
+
+    /// Reads the low 255 bits of `bytes`.
+    pub(crate) fn from_bytes(bytes: &[u8; 32]) -> Self {
+        let load = |i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().expect("8 bytes"));
+        Self([
+            load(0) & MASK,
+            (load(6) >> 3) & MASK,
+            (load(12) >> 6) & MASK,
+            (load(19) >> 1) & MASK,
+            (load(24) >> 12) & MASK,
+        ])
+    }
+
+    /// The canonical encoding, fully reduced below p.
+    pub(crate) fn to_bytes(self) -> [u8; 32] {
+        let mut limbs = Self::carry(self.0).0;
+
+        // limbs + 19 overflows 2^255 exactly when limbs >= p
+        let mut q = (limbs[0] + 19) >> 51;
+        for limb in &limbs[1..] {
+            q = (limb + q) >> 51;
+        }
+        limbs[0] += 19 * q;
+        for i in 0..4 {
+            limbs[i + 1] += limbs[i] >> 51;
+            limbs[i] &= MASK;
+        }
+        limbs[4] &= MASK;
+
+        let mut bytes = [0u8; 32];
+        let (mut acc, mut bits, mut i) = (0u128, 0, 0);
+        for limb in limbs {
+            acc |= (limb as u128) << bits;
+            bits += 51;
+            while bits >= 8 {
+                bytes[i] = acc as u8;
+                acc >>= 8;
+                bits -= 8;
+                i += 1;
+            }
+        }
+        bytes[i] = acc as u8;
+        bytes
+    }
+
+    pub(crate) fn is_negative(self) -> bool {
+        self.to_bytes()[0] & 1 == 1
+    }
+
+    pub(crate) fn square(self) -> Self {
+        self * self
+    }
+
+    fn pow(self, exponent: &[u8; 32]) -> Self {
+        let mut result = Self::ONE;
+        for bit in (0..256).rev() {
+            result = result.square();
+            if exponent[bit / 8] >> (bit % 8) & 1 == 1 {
+                result = result * self;
+            }
+        }
+        result
+    }
+
+    pub(crate) fn invert(self) -> Self {
+        self.pow(&P_MINUS_2)
+    }
+
+    /// Inverts every element with a single inversion. No element may be zero.
+    pub(crate) fn batch_invert(elements: &mut [Self], scratch: &mut Vec<Self>) {
+        scratch.clear();
+        let mut acc = Self::ONE;
+        for element in elements.iter() {
+            scratch.push(acc);
+            acc = acc * *element;
+        }
+
+        let mut acc = acc.invert();
+        for (element, product) in elements.iter_mut().zip(scratch.iter()).rev() {
+            let inverse = acc * *product;
+            acc = acc * *element;
+            *element = inverse;
+        }
+    }
+
+    /// The affine coordinates of a compressed edwards25519 point, `None` if the
+    /// bytes aren't one.
+    pub(crate) fn decompress(bytes: &[u8; 32]) -> Option<(Self, Self)> {
+        let y = Self::from_bytes(bytes);
+        let yy = y.square();
+        let u = yy - Self::ONE;
+        let v = EDWARDS_D * yy + Self::ONE;
+
+        // x = sqrt(u / v) = u v^3 (u v^7)^((p - 5) / 8), up to a factor of sqrt(-1)
+        let v3 = v.square() * v;
+        let mut x = u * v3 * (u * v3.square() * v).pow(&P_MINUS_5_OVER_8);
+        let vxx = (v * x.square()).to_bytes();
+        if vxx == (-u).to_bytes() {
+            x = x * SQRT_M1;
+        } else if vxx != u.to_bytes() {
+            return None;
+        }
+
+        if x.is_negative() != (bytes[31] >> 7 == 1) {
+            x = -x;
+        }
+        Some((x, y))
+    }
+
+    fn carry(mut limbs: [u64; 5]) -> Self {
+        let carries = limbs.map(|limb| limb >> 51);
+        for limb in &mut limbs {
+            *limb &= MASK;
+        }
+        limbs[0] += carries[4] * 19;
+        for i in 1..5 {
+            limbs[i] += carries[i - 1];
+        }
+        Self(limbs)
+    }
+}
+
+impl Add for FieldElement {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::carry(std::array::from_fn(|i| self.0[i] + rhs.0[i]))
+    }
+}
+
+impl Sub for FieldElement {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        // Adding 16p keeps every limb positive
+        const SIXTEEN_P: [u64; 5] = [
+            36028797018963664,
+            36028797018963952,
+            36028797018963952,
+            36028797018963952,
+            36028797018963952,
+        ];
+        Self::carry(std::array::from_fn(|i| self.0[i] + SIXTEEN_P[i] - rhs.0[i]))
+    }
+}
+
+impl Neg for FieldElement {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::ZERO - self
+    }
+}
+
+impl Mul for FieldElement {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let (a, b) = (self.0, rhs.0);
+        let m = |x: u64, y: u64| x as u128 * y as u128;
+        // 2^255 = 19, so limbs past the top wrap around times 19
+        let b19 = [0, b[1] * 19, b[2] * 19, b[3] * 19, b[4] * 19];
+
+        let c0 = m(a[0], b[0]) + m(a[4], b19[1]) + m(a[3], b19[2]) + m(a[2], b19[3]) + m(a[1], b19[4]);
+        let mut c1 = m(a[1], b[0]) + m(a[0], b[1]) + m(a[4], b19[2]) + m(a[3], b19[3]) + m(a[2], b19[4]);
+        let mut c2 = m(a[2], b[0]) + m(a[1], b[1]) + m(a[0], b[2]) + m(a[4], b19[3]) + m(a[3], b19[4]);
+        let mut c3 = m(a[3], b[0]) + m(a[2], b[1]) + m(a[1], b[2]) + m(a[0], b[3]) + m(a[4], b19[4]);
+        let mut c4 = m(a[4], b[0]) + m(a[3], b[1]) + m(a[2], b[2]) + m(a[1], b[3]) + m(a[0], b[4]);
+
+        c1 += c0 >> 51;
+        c2 += c1 >> 51;
+        c3 += c2 >> 51;
+        c4 += c3 >> 51;
+        let mut limbs = [c0, c1, c2, c3, c4].map(|c| c as u64 & MASK);
+        limbs[0] += (c4 >> 51) as u64 * 19;
+        limbs[1] += limbs[0] >> 51;
+        limbs[0] &= MASK;
+        Self(limbs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE, scalar::Scalar};
+
+    #[test]
+    fn test_decompress_and_invert_agree_with_dalek() {
+        for k in [1u64, 2, 9, 123_456_789] {
+            let point = (&Scalar::from(k) * &ED25519_BASEPOINT_TABLE).compress().to_bytes();
+            let (x, y) = FieldElement::decompress(&point).unwrap();
+
+            // Encoding the affine point again gives the same bytes
+            let mut encoded = y.to_bytes();
+            encoded[31] |= (x.is_negative() as u8) << 7;
+            assert_eq!(encoded, point);
+
+            let mut elements = [x, y, x * y];
+            FieldElement::batch_invert(&mut elements, &mut Vec::new());
+            assert_eq!((elements[0] * x).to_bytes(), FieldElement::ONE.to_bytes());
+            assert_eq!((elements[2] * x * y).to_bytes(), FieldElement::ONE.to_bytes());
+            assert_eq!(elements[1].to_bytes(), y.invert().to_bytes());
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::pattern::{CompiledPattern, Position};
+use crate::prefix::PrefixRanges;
+use crate::suffix::{ResidueReducer, SuffixTargets};
+
+/// Rules out raw addresses that cannot match before they are base58 encoded, from
+/// the start and end parts of each compound pattern. Every pattern needs at least
+/// one such part, otherwise any address could match and there is nothing to filter.
+#[derive(Debug, Clone)]
+pub(crate) struct AddressFilter {
+    /// Present when any pattern has an end part; its modulus covers all of them.
+    reducer: Option<ResidueReducer>,
+    /// Per compound pattern, checks that must all pass for it to match.
+    compounds: Vec<Vec<PartCheck>>,
+}
+
+#[derive(Debug, Clone)]
+enum PartCheck {
+    Prefix(PrefixRanges),
+    Suffix(SuffixTargets),
+}
+
+impl AddressFilter {
+    pub(crate) fn new(compiled: &[Vec<CompiledPattern>]) -> Option<Self> {
+        let mut compounds = Vec::with_capacity(compiled.len());
+        for parts in compiled {
+            let mut checks: Vec<PartCheck> = parts
+                .iter()
+                .filter_map(|part| match part.position {
+                    Position::Start | Position::At(0) => {
+                        PrefixRanges::new(&part.classes).map(PartCheck::Prefix)
+                    }
+                    Position::End => SuffixTargets::new(&part.classes).map(PartCheck::Suffix),
+                    _ => None,
+                })
+                .collect();
+            if checks.is_empty() {
+                return None;
+            }
+            // Range lookups are cheaper than reducing the address
+            checks.sort_by_key(|check| matches!(check, PartCheck::Suffix(_)));
+            compounds.push(checks);
+        }
+
+        let reducer = compounds
+            .iter()
+            .flatten()
+            .filter_map(|check| match check {
+                PartCheck::Suffix(targets) => Some(targets.modulus()),
+                PartCheck::Prefix(_) => None,
+            })
+            .max()
+            .map(ResidueReducer::new);
+
+        Some(Self { reducer, compounds })
+    }
+
+    /// Whether any pattern can match the address. A `true` still has to be
+    /// confirmed against the encoded address.
+    pub(crate) fn may_match(&self, address: &[u8; 32]) -> bool {
+        let mut residue = None;
+        self.compounds.iter().any(|checks| {
+            checks.iter().all(|check| match check {
+                PartCheck::Prefix(ranges) => ranges.contains(address),
+                PartCheck::Suffix(targets) => targets.contains(*residue.get_or_insert_with(|| {
+                    self.reducer
+                        .as_ref()
+                        .expect("reducer exists for end parts")
+                        .residue(address)
+                })),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{base58, CompoundPattern, PatternSet, PatternSpec};
+
+    fn assert_agrees(patterns: &[CompoundPattern], exact: bool) {
+        let set = PatternSet::new(patterns).unwrap();
+        let filter = AddressFilter::new(set.compiled()).unwrap();
+        let mut out = [0u8; base58::MAX_ENCODED_LEN];
+
+        for _ in 0..20_000 {
+            let address = rand::random::<[u8; 32]>();
+            let matched = set.find_match_bytes(base58::encode_32(&address, &mut out)).is_some();
+            if exact {
+                assert_eq!(filter.may_match(&address), matched, "{:?}", patterns);
+            } else {
+                assert!(filter.may_match(&address) || !matched, "{:?}", patterns);
+            }
+        }
+    }
+
+    #[test]
+    fn test_prefix_ranges_agree_with_encoded_match() {
+        for (pattern, case_insensitive) in [("5", false), ("h", true), ("[A-C]?", false), ("Gz", true)] {
+            assert_agrees(&[PatternSpec::new(pattern, Position::Start, case_insensitive).into()], true);
+        }
+        // Too many spellings to expand, only the leading characters are checked
+        assert_agrees(&[PatternSpec::new("[2-9A-H][2-9A-H][2-9A-H]ab", Position::Start, true).into()], false);
+    }
+
+    #[test]
+    fn test_compound_checks_combine() {
+        let both = CompoundPattern::prefix_suffix(
+            PatternSpec::new("[2-9]", Position::Start, false),
+            PatternSpec::new("x", Position::End, true),
+        );
+        assert_agrees(&[both, PatternSpec::new("z", Position::End, false).into()], true);
+    }
+
+    #[test]
+    fn test_filter_needs_a_check_per_pattern() {
+        let end = PatternSpec::new("gems", Position::End, true);
+        let contains = PatternSpec::new("Tx", Position::Contains, false);
+        let set = PatternSet::new(&[end.clone().into(), contains.into()]).unwrap();
+        assert!(AddressFilter::new(set.compiled()).is_none());
+
+        let ones = PatternSpec::new("11", Position::Start, false);
+        let set = PatternSet::new(&[end.into(), ones.into()]).unwrap();
+        assert!(AddressFilter::new(set.compiled()).is_none());
+    }
+}
@@ -0,0 +1,128 @@
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{keypair_from_seed, Keypair, Signer};
+
+use crate::base58::MAX_ENCODED_LEN;
+use crate::{BatchMatch, ChunkBest, PatternSet, SearchStats};
+
+/// Attempt indices per batch. Every candidate costs a scalar multiplication, so
+/// batches are far smaller than those of seed searches to keep stop checks frequent.
+pub(crate) const KEYPAIR_BATCH_SIZE: u64 = 1 << 16;
+const KEYPAIR_CHUNK_SIZE: u64 = 1 << 8;
+
+/// Keeps the mint keys unrelated to the base key and seed salt of the same secret.
+const MINT_KEY_TAG: &[u8] = b"token22-vanity mint keypair";
+const WALLET_KEY_TAG: &[u8] = b"token22-vanity wallet keypair";
+
+/// Maps attempt indices to mint keypairs derived from the secret key of the
+/// search's base keypair, so master seeds, checkpoints and attempt indices work
+/// for keypair searches the way they do for seeds.
+#[derive(Clone)]
+pub(crate) struct KeypairSchedule {
+    /// Tag and base secret key, hashed once.
+    state: Sha256,
+}
+
+impl KeypairSchedule {
+    pub(crate) fn new(base_keypair: &Keypair) -> Self {
+        Self::tagged(MINT_KEY_TAG, base_keypair)
+    }
+
+    /// The wallet keypairs of ATA searches, unrelated to the mint keypairs.
+    pub(crate) fn wallets(base_keypair: &Keypair) -> Self {
+        Self::tagged(WALLET_KEY_TAG, base_keypair)
+    }
+
+    fn tagged(tag: &[u8], base_keypair: &Keypair) -> Self {
+        let mut state = Sha256::new();
+        state.update(tag);
+        state.update(&base_keypair.to_bytes()[..32]);
+        Self { state }
+    }
+
+    pub(crate) fn keypair(&self, index: u64) -> Keypair {
+        let seed: [u8; 32] = self.state.clone().chain_update(index.to_le_bytes()).finalize().into();
+        keypair_from_seed(&seed).expect("any 32 bytes are an ed25519 secret key")
+    }
+
+    /// Grinds the mint keypairs of `start..start + count`. Like `search_batch`, the
+    /// lowest matching index wins and ends the batch.
+    pub(crate) fn search_batch(
+        &self,
+        start: u64,
+        count: u64,
+        patterns: &PatternSet,
+        stats: &SearchStats,
+    ) -> Option<BatchMatch> {
+        self.search_batch_with(start, count, patterns, stats, |pubkey| Some(pubkey.to_bytes()))
+    }
+
+    /// Like `search_batch`, matching the address `derive` makes of each public key
+    /// instead of the key itself. Keys without an address are skipped.
+    pub(crate) fn search_batch_with(
+        &self,
+        start: u64,
+        count: u64,
+        patterns: &PatternSet,
+        stats: &SearchStats,
+        derive: impl Fn(&Pubkey) -> Option<[u8; 32]> + Sync,
+    ) -> Option<BatchMatch> {
+        let result = (0..count.div_ceil(KEYPAIR_CHUNK_SIZE))
+            .into_par_iter()
+            .find_map_first(|chunk| {
+                let chunk_start = start + chunk * KEYPAIR_CHUNK_SIZE;
+                let chunk_end = (chunk_start + KEYPAIR_CHUNK_SIZE).min(start + count);
+                let mut encoded = [0u8; MAX_ENCODED_LEN];
+                let mut best = ChunkBest::new(stats);
+
+                for index in chunk_start..chunk_end {
+                    let Some(address) = derive(&self.keypair(index).pubkey()) else {
+                        continue;
+                    };
+                    if let Some(pattern) = patterns.find_match_address(&address, &mut encoded) {
+                        return Some(BatchMatch {
+                            attempt_index: index,
+                            owner: 0,
+                            address: Pubkey::new_from_array(address),
+                            pattern,
+                        });
+                    }
+
+                    best.offer(patterns, &address, index, 0);
+                }
+
+                best.finish();
+                None
+            });
+
+        let attempts = result.map_or(count, |found| found.attempt_index - start + 1);
+        stats.attempts.fetch_add(attempts, std::sync::atomic::Ordering::Relaxed);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PatternSpec, Position};
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn test_keypair_search_finds_lowest_matching_keypair() {
+        let patterns = PatternSet::new(&[PatternSpec::new("a", Position::End, false).into()]).unwrap();
+        let schedule = KeypairSchedule::new(&Keypair::new());
+        let stats = SearchStats::new();
+
+        let found = schedule.search_batch(0, 2000, &patterns, &stats).unwrap();
+        assert_eq!(schedule.keypair(found.attempt_index).pubkey(), found.address);
+        assert!(found.address.to_string().ends_with('a'));
+        assert_eq!(stats.attempts.load(Ordering::Relaxed), found.attempt_index + 1);
+        for earlier in 0..found.attempt_index {
+            assert!(!schedule.keypair(earlier).pubkey().to_string().ends_with('a'));
+        }
+
+        // Another base key gives other keypairs
+        assert_ne!(KeypairSchedule::new(&Keypair::new()).keypair(0).pubkey(), schedule.keypair(0).pubkey());
+    }
+}
@@ -1,125 +1,684 @@
 use anyhow::{anyhow, Result};
-use log::{info, warn, debug};
+use log::{info, debug};
+#[cfg(feature = "gpu")]
+use log::warn;
 use rayon::prelude::*;
 use solana_program::pubkey::Pubkey;
 use solana_sdk::signature::{Keypair, Signer};
 use std::{
     sync::{
         atomic::{AtomicBool, AtomicU64, Ordering},
-        Arc,
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex,
     },
-    time::Instant,
+    time::{Duration, Instant},
 };
-use rand::Rng;
 use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
 
 mod metal;
-use metal::MetalDevice;
+
+mod pattern;
+pub use pattern::{CompoundPattern, InvalidChar, PatternError, PatternSet, PatternSpec, Position};
+
+mod estimate;
+pub use estimate::{estimate_difficulty, Difficulty};
+
+mod base58;
+use base58::MAX_ENCODED_LEN;
+
+mod seed;
+pub use seed::{SeedAlphabet, SeedFormat};
+use seed::{base_keypair_from_master_seed, SeedSchedule};
+
+mod filter;
+mod prefix;
+mod suffix;
+
+mod bench;
+pub use bench::{run_benchmark, BenchReport};
+
+mod outcome;
+pub use outcome::{LongestMatch, MatchCollection, PartialMatch, SearchOutcome, SearchSummary, StopReason};
+
+mod partial;
+
+mod callback;
+pub use callback::Callback;
+
+mod progress;
+pub use progress::{Backend, ProgressCallback, ProgressEvent};
+
+mod owner;
+pub use owner::OwnerProgram;
+
+mod mode;
+pub use mode::SearchMode;
+
+mod keypair;
+use keypair::{KeypairSchedule, KEYPAIR_BATCH_SIZE};
+
+mod field;
+mod expanded;
+pub use expanded::{ExpandedKeypair, EXPANDED_KEYPAIR_LENGTH};
+use expanded::ExpandedKeypairSchedule;
+
+mod pda;
+pub use pda::{PdaAddress, PdaSeeds};
+use pda::PdaGrinder;
+
+mod ata;
+use ata::AtaGrinder;
+
+mod searcher;
+pub use searcher::VanitySearcher;
+
+mod stream;
+pub use stream::{SearchEvent, SearchStream};
+
+mod checkpoint;
+pub use checkpoint::{Checkpoint, CheckpointOptions};
+use checkpoint::CheckpointWriter;
 
 const CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
-const TOKEN_PROGRAM_ID: Pubkey = solana_program::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
-const BATCH_SIZE: usize = 1_000_000;
+const BATCH_SIZE: u64 = 1 << 20;
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+/// Attempts each rayon task runs before another task can pick up the rest of a batch.
+const CHUNK_SIZE: usize = 1 << 13;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VanityAddressResult {
+    pub mode: SearchMode,
+    /// The base key of a seed search, the mint key itself for keypair searches, the
+    /// program of a PDA search and the wallet of an ATA search.
     pub base_pubkey: String,
+    /// The ground seed, empty for keypair searches.
     pub seed: String,
     pub token_address: String,
-    pub keypair_json: String,
+    /// Program owning `token_address`, one of the search's owners.
+    pub owner: OwnerProgram,
+    /// Keypair of `base_pubkey` in Solana CLI format. `None` for pubkey-only searches.
+    pub keypair_json: Option<String>,
     pub time_taken: f64,
     pub attempts: u64,
+    pub matched_pattern: usize,
+    /// Index of the matching seed in the search's seed schedule. Together with the
+    /// master seed it reproduces the match. `None` for GPU results.
+    pub attempt_index: Option<u64>,
+    /// All seeds and the canonical bump of a PDA match.
+    pub pda: Option<PdaAddress>,
+    /// Mint of the token account an ATA search found.
+    pub mint: Option<String>,
+}
+
+/// Options for `find_vanity_address_with_options`.
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    /// What kind of address to grind (default: seed addresses).
+    pub mode: SearchMode,
+    /// Use the Metal GPU if the patterns allow it. Searches on a `VanitySearcher`
+    /// only use one if the searcher was built with it.
+    pub use_gpu: bool,
+    /// Number of CPU threads (default: num_cpus). Searches on a `VanitySearcher`
+    /// run on its thread pool instead.
+    pub threads: Option<usize>,
+    /// Derive the base keypair and the seeds from this secret instead of at random,
+    /// so the search always ends on the lowest matching attempt index. Anyone holding
+    /// the master seed holds the base keypair.
+    pub master_seed: Option<[u8; 32]>,
+    /// The base key to derive addresses from (default: a fresh keypair).
+    pub base_key: BaseKey,
+    /// Length, alphabet and prefix of the generated seeds.
+    pub seed_format: SeedFormat,
+    /// Program and fixed seeds of a PDA search, which grinds one seed among them.
+    pub pda: Option<PdaSeeds>,
+    /// Mint an ATA search grinds wallets for. The owner is the ATA's token program.
+    pub ata_mint: Option<Pubkey>,
+    /// Programs that may own the addresses. Each seed is tried with every one of
+    /// them (default: Token-2022).
+    pub owners: Vec<OwnerProgram>,
+    /// Periodically save the search so it can be resumed. A search that stops
+    /// early saves a last checkpoint, one that finds every match deletes it.
+    pub checkpoint: Option<CheckpointOptions>,
+    /// Stops the search when cancelled.
+    pub cancel: Option<CancelHandle>,
+    /// Give up after this much wall-clock time in this run.
+    pub timeout: Option<Duration>,
+    /// Give up after this many attempts, counting those of a resumed checkpoint.
+    pub max_attempts: Option<u64>,
+    /// Keep the candidate closest to a pattern for `SearchSummary::best_match` and
+    /// progress events. Always on for searches with a cancel handle, timeout or
+    /// attempt budget. Scoring every miss slows the search down, so others only
+    /// track it when set here.
+    pub track_best: bool,
+    /// Called with a progress event every `progress_interval` while searching, and
+    /// once more with the final count when the search stops.
+    pub on_progress: Option<ProgressCallback>,
+    /// Time between progress events (default: 1s).
+    pub progress_interval: Option<Duration>,
+    /// Called with each match as soon as it is found.
+    pub on_match: Option<MatchCallback>,
+}
+
+impl SearchOptions {
+    /// `owners` without duplicates, Token-2022 when empty.
+    fn owner_programs(&self) -> Vec<OwnerProgram> {
+        let mut owners = Vec::new();
+        for owner in &self.owners {
+            if !owners.contains(owner) {
+                owners.push(*owner);
+            }
+        }
+        if owners.is_empty() {
+            owners.push(OwnerProgram::Token2022);
+        }
+        owners
+    }
+}
+
+/// Receives matches on the searching thread.
+pub type MatchCallback = Callback<VanityAddressResult>;
+
+/// Where the base key of a search comes from.
+#[derive(Debug, Clone, Default)]
+pub enum BaseKey {
+    /// A fresh keypair, or the one derived from the master seed.
+    #[default]
+    Generate,
+    /// An existing keypair, e.g. one that is already funded.
+    Keypair(Arc<Keypair>),
+    /// Only the public key. The search never holds secret material and its
+    /// results carry no keypair, so it can run on untrusted machines.
+    Pubkey(Pubkey),
+}
+
+/// The base key of a running search. Pubkey-only searches have no keypair.
+pub(crate) struct SearchBase {
+    pubkey: Pubkey,
+    keypair: Option<Arc<Keypair>>,
+}
+
+impl SearchBase {
+    fn from_keypair(keypair: Arc<Keypair>) -> Self {
+        Self {
+            pubkey: keypair.pubkey(),
+            keypair: Some(keypair),
+        }
+    }
+
+    fn from_pubkey(pubkey: Pubkey) -> Self {
+        Self { pubkey, keypair: None }
+    }
+
+    pub(crate) fn pubkey(&self) -> &Pubkey {
+        &self.pubkey
+    }
+
+    pub(crate) fn keypair(&self) -> Option<&Keypair> {
+        self.keypair.as_deref()
+    }
+
+    fn keypair_json(&self) -> Option<String> {
+        self.keypair.as_deref().map(keypair_json)
+    }
+}
+
+/// `keypair` in Solana CLI format.
+fn keypair_json(keypair: &Keypair) -> String {
+    json_bytes(&keypair.to_bytes())
+}
+
+fn json_bytes(bytes: &[u8]) -> String {
+    format!("[{}]", bytes.iter().map(|b| b.to_string()).collect::<Vec<String>>().join(","))
+}
+
+/// How a search turns attempt indices into candidate addresses.
+enum Grinder<'a> {
+    /// One `create_with_seed` address per seed of `schedule` and owner.
+    Seed {
+        base: &'a SearchBase,
+        schedule: &'a SeedSchedule,
+        owner_ids: Vec<Pubkey>,
+    },
+    /// One mint keypair per index.
+    Keypair(KeypairSchedule),
+    /// One expanded keypair per index, each one point addition from the last.
+    ExpandedKeypair(ExpandedKeypairSchedule),
+    /// One program address per seed of the schedule, at its canonical bump.
+    Pda(PdaGrinder<'a>),
+    /// One wallet keypair and its associated token account per index.
+    Ata(AtaGrinder),
+}
+
+impl<'a> Grinder<'a> {
+    fn new(
+        options: &SearchOptions,
+        base: &'a SearchBase,
+        schedule: &'a SeedSchedule,
+        owners: &[OwnerProgram],
+    ) -> Result<Self> {
+        match options.mode {
+            SearchMode::Seed => Ok(Grinder::Seed {
+                base,
+                schedule,
+                owner_ids: owners.iter().map(OwnerProgram::program_id).collect(),
+            }),
+            SearchMode::Keypair | SearchMode::ExpandedKeypair => {
+                let base_keypair = base
+                    .keypair()
+                    .ok_or_else(|| anyhow!("A keypair search derives its mint keypairs from a secret base key"))?;
+                Ok(match options.mode {
+                    SearchMode::Keypair => Grinder::Keypair(KeypairSchedule::new(base_keypair)),
+                    _ => Grinder::ExpandedKeypair(ExpandedKeypairSchedule::new(base_keypair)),
+                })
+            }
+            SearchMode::Pda => {
+                let pda = options
+                    .pda
+                    .clone()
+                    .ok_or_else(|| anyhow!("A PDA search needs the program and its fixed seeds"))?;
+                pda.validate()?;
+                Ok(Grinder::Pda(PdaGrinder::new(pda, schedule)))
+            }
+            SearchMode::Ata => {
+                let base_keypair = base
+                    .keypair()
+                    .ok_or_else(|| anyhow!("An ATA search derives its wallet keypairs from a secret base key"))?;
+                let mint = options.ata_mint.ok_or_else(|| anyhow!("An ATA search needs the mint"))?;
+                Ok(Grinder::Ata(AtaGrinder::new(base_keypair, mint, owners[0].program_id())))
+            }
+        }
+    }
+
+    fn batch_size(&self) -> u64 {
+        match self {
+            Grinder::Seed { .. } | Grinder::ExpandedKeypair(_) | Grinder::Pda(_) => BATCH_SIZE,
+            Grinder::Keypair(_) | Grinder::Ata(_) => KEYPAIR_BATCH_SIZE,
+        }
+    }
+
+    fn addresses_per_index(&self) -> u64 {
+        match self {
+            Grinder::Seed { owner_ids, .. } => owner_ids.len() as u64,
+            Grinder::Keypair(_) | Grinder::ExpandedKeypair(_) | Grinder::Pda(_) | Grinder::Ata(_) => 1,
+        }
+    }
+
+    /// Number of attempt indices there are, `None` when every u64 is one.
+    fn capacity(&self) -> Option<u64> {
+        match self {
+            Grinder::Seed { schedule, .. } => schedule.capacity(),
+            Grinder::Pda(pda) => pda.capacity(),
+            Grinder::Keypair(_) | Grinder::ExpandedKeypair(_) | Grinder::Ata(_) => None,
+        }
+    }
+
+    fn search_batch(&self, start: u64, count: u64, patterns: &PatternSet, stats: &SearchStats) -> Option<BatchMatch> {
+        match self {
+            Grinder::Seed { base, schedule, owner_ids } => {
+                search_batch(base.pubkey(), owner_ids, schedule, start, count, patterns, stats)
+            }
+            Grinder::Keypair(keypairs) => keypairs.search_batch(start, count, patterns, stats),
+            Grinder::ExpandedKeypair(keypairs) => keypairs.search_batch(start, count, patterns, stats),
+            Grinder::Pda(pda) => pda.search_batch(start, count, patterns, stats),
+            Grinder::Ata(wallets) => wallets.search_batch(start, count, patterns, stats),
+        }
+    }
+
+    fn seed(&self, index: u64) -> String {
+        match self {
+            Grinder::Seed { schedule, .. } => schedule.cursor(index).to_string(),
+            Grinder::Pda(pda) => pda.seed(index),
+            Grinder::Keypair(_) | Grinder::ExpandedKeypair(_) | Grinder::Ata(_) => String::new(),
+        }
+    }
+
+    fn mint(&self) -> Option<String> {
+        match self {
+            Grinder::Ata(wallets) => Some(wallets.mint().to_string()),
+            _ => None,
+        }
+    }
+
+    /// The seeds and bump behind a PDA match.
+    fn pda(&self, index: Option<u64>) -> Option<PdaAddress> {
+        match self {
+            Grinder::Pda(pda) => Some(pda.address(index.expect("PDA searches run on the CPU"))),
+            _ => None,
+        }
+    }
+
+    /// The key a match at `index` belongs to and its keypair as a JSON byte array,
+    /// in Solana CLI format unless it is an expanded keypair. GPU matches, which have
+    /// no index, are seed matches.
+    fn keys(&self, index: Option<u64>) -> (Pubkey, Option<String>) {
+        match self {
+            Grinder::Seed { base, .. } => (*base.pubkey(), base.keypair_json()),
+            Grinder::Keypair(keypairs) => {
+                let keypair = keypairs.keypair(index.expect("keypair searches run on the CPU"));
+                (keypair.pubkey(), Some(keypair_json(&keypair)))
+            }
+            Grinder::ExpandedKeypair(keypairs) => {
+                let keypair = keypairs.keypair(index.expect("keypair searches run on the CPU"));
+                (keypair.pubkey(), Some(json_bytes(&keypair.to_bytes())))
+            }
+            Grinder::Pda(pda) => (pda.program_id(), None),
+            Grinder::Ata(wallets) => {
+                let wallet = wallets.wallet(index.expect("ATA searches run on the CPU"));
+                (wallet.pubkey(), Some(keypair_json(&wallet)))
+            }
+        }
+    }
+}
+
+/// A candidate address regenerated from a master seed and attempt index.
+#[derive(Debug)]
+pub struct Candidate {
+    pub base_keypair: Keypair,
+    pub seed: String,
+    pub token_address: Pubkey,
+}
+
+/// Regenerates the candidate a search from `master_seed` with `seed_format` and
+/// `owner` tried at `attempt_index`, e.g. to verify a claimed match on another machine.
+pub fn reproduce_candidate(
+    master_seed: &[u8; 32],
+    seed_format: &SeedFormat,
+    owner: &OwnerProgram,
+    attempt_index: u64,
+) -> Result<Candidate> {
+    let base_keypair = base_keypair_from_master_seed(master_seed)?;
+    let schedule = SeedSchedule::from_master_seed(master_seed, seed_format)?;
+    if schedule.capacity().is_some_and(|capacity| attempt_index >= capacity) {
+        return Err(anyhow!("Attempt index {} is outside the seed format", attempt_index));
+    }
+    let seed = schedule
+        .cursor(attempt_index)
+        .to_string();
+    let token_address = Pubkey::create_with_seed(&base_keypair.pubkey(), &seed, &owner.program_id())?;
+
+    Ok(Candidate {
+        base_keypair,
+        seed,
+        token_address,
+    })
+}
+
+/// Regenerates the mint keypair a keypair search from `master_seed` tried at
+/// `attempt_index`.
+pub fn reproduce_keypair(master_seed: &[u8; 32], attempt_index: u64) -> Result<Keypair> {
+    let base_keypair = base_keypair_from_master_seed(master_seed)?;
+    Ok(KeypairSchedule::new(&base_keypair).keypair(attempt_index))
+}
+
+/// Regenerates the key an expanded keypair search from `master_seed` tried at
+/// `attempt_index`.
+pub fn reproduce_expanded_keypair(master_seed: &[u8; 32], attempt_index: u64) -> Result<ExpandedKeypair> {
+    let base_keypair = base_keypair_from_master_seed(master_seed)?;
+    Ok(ExpandedKeypairSchedule::new(&base_keypair).keypair(attempt_index))
+}
+
+/// Regenerates the wallet keypair an ATA search from `master_seed` tried at
+/// `attempt_index`.
+pub fn reproduce_ata_wallet(master_seed: &[u8; 32], attempt_index: u64) -> Result<Keypair> {
+    let base_keypair = base_keypair_from_master_seed(master_seed)?;
+    Ok(KeypairSchedule::wallets(&base_keypair).keypair(attempt_index))
+}
+
+/// Regenerates the seeds and bump a PDA search from `master_seed` with
+/// `seed_format` tried at `attempt_index`.
+pub fn reproduce_pda(
+    master_seed: &[u8; 32],
+    seed_format: &SeedFormat,
+    pda: &PdaSeeds,
+    attempt_index: u64,
+) -> Result<PdaAddress> {
+    pda.validate()?;
+    let schedule = SeedSchedule::from_master_seed(master_seed, seed_format)?;
+    if schedule.capacity().is_some_and(|capacity| attempt_index >= capacity) {
+        return Err(anyhow!("Attempt index {} is outside the seed format", attempt_index));
+    }
+    Ok(PdaGrinder::new(pda.clone(), &schedule).address(attempt_index))
+}
+
+/// Stops a running search from another thread or task.
+#[derive(Debug, Clone, Default)]
+pub struct CancelHandle(Arc<AtomicBool>);
+
+impl CancelHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
 }
 
 #[derive(Clone)]
 struct SearchStats {
     attempts: Arc<AtomicU64>,
-    found: Arc<AtomicBool>,
+    best: Arc<Mutex<Option<BestCandidate>>>,
+    /// Whether the grinders score their misses for `best`, a second pass over every
+    /// address that only best effort searches pay for.
+    track_best: bool,
+}
+
+/// The closest miss of a search so far, by `PatternSet::partial_score`.
+#[derive(Debug, Clone)]
+struct BestCandidate {
+    score: usize,
+    attempt_index: u64,
+    /// Index into the search's owners.
+    owner: usize,
+    address: Pubkey,
+    compound: usize,
 }
 
 impl SearchStats {
     fn new() -> Self {
         Self {
             attempts: Arc::new(AtomicU64::new(0)),
-            found: Arc::new(AtomicBool::new(false)),
+            best: Arc::new(Mutex::new(None)),
+            track_best: false,
+        }
+    }
+
+    /// Stats that also keep the closest miss of the search.
+    fn tracking_best() -> Self {
+        Self {
+            track_best: true,
+            ..Self::new()
         }
     }
+
+    fn offer_best(&self, candidate: BestCandidate) {
+        let mut best = self.best.lock().expect("best candidate lock poisoned");
+        // Ties go to the lower index, which keeps master seed searches reproducible
+        let better = best.as_ref().is_none_or(|best| {
+            (candidate.score, std::cmp::Reverse((candidate.attempt_index, candidate.owner)))
+                > (best.score, std::cmp::Reverse((best.attempt_index, best.owner)))
+        });
+        if better {
+            *best = Some(candidate);
+        }
+    }
+
+    fn best(&self) -> Option<BestCandidate> {
+        self.best.lock().expect("best candidate lock poisoned").clone()
+    }
 }
 
-fn matches_pattern(address: &str, pattern: &str, case_insensitive: bool, position: &str) -> bool {
-    let (address, pattern) = if case_insensitive {
-        (address.to_lowercase(), pattern.to_lowercase())
-    } else {
-        (address.to_string(), pattern.to_string())
-    };
+/// The closest miss of one chunk of a batch, handed to `SearchStats` when the chunk
+/// is done. Scores nothing unless the stats track the best candidate.
+struct ChunkBest<'a> {
+    stats: &'a SearchStats,
+    best: Option<BestCandidate>,
+}
 
-    match position {
-        "start" => address.starts_with(&pattern),
-        "end" => address.ends_with(&pattern),
-        _ => false,
+impl<'a> ChunkBest<'a> {
+    fn new(stats: &'a SearchStats) -> Self {
+        Self { stats, best: None }
+    }
+
+    #[inline]
+    fn offer(&mut self, patterns: &PatternSet, address: &[u8; 32], attempt_index: u64, owner: usize) {
+        if !self.stats.track_best {
+            return;
+        }
+        let (compound, score) = patterns.partial_score(address);
+        if score > self.best.as_ref().map_or(0, |best| best.score) {
+            self.best = Some(BestCandidate {
+                score,
+                attempt_index,
+                owner,
+                address: Pubkey::new_from_array(*address),
+                compound,
+            });
+        }
+    }
+
+    fn finish(self) {
+        if let Some(best) = self.best {
+            self.stats.offer_best(best);
+        }
     }
 }
 
+/// Checks a single address against a pattern. `position` is parsed like `--position`
+/// ("start", "end", "contains" or "at:<offset>"); an unparseable position never matches.
+pub fn matches_pattern(address: &str, pattern: &str, case_insensitive: bool, position: &str) -> bool {
+    match position.parse::<Position>() {
+        Ok(position) => PatternSpec::new(pattern, position, case_insensitive).matches(address),
+        Err(_) => false,
+    }
+}
+
+/// A match of `search_batch`: the seed's attempt index, the index of the owner in
+/// the owner list, the address and the index of the matched pattern.
+#[derive(Debug, Clone, Copy)]
+struct BatchMatch {
+    attempt_index: u64,
+    owner: usize,
+    address: Pubkey,
+    pattern: usize,
+}
+
+/// Grinds the attempt indices `start..start + count`, deriving one address per owner
+/// in `owners` from each seed. Owners must pass `OwnerProgram::validate`.
+/// `create_with_seed` is `sha256(base || seed || owner)`, so the base key is hashed
+/// once, each seed once on top of it, and each owner only clones that state. Seeds
+/// advance in place and addresses are base58 encoded on the stack, so nothing is
+/// allocated until a match turns up. A match ends the batch, and only the addresses
+/// up to and including it count as attempts.
 fn search_batch(
-    base_keypair: &Keypair,
-    pattern: &str,
-    position: &str,
-    case_insensitive: bool,
+    base_pubkey: &Pubkey,
+    owners: &[Pubkey],
+    schedule: &SeedSchedule,
+    start: u64,
+    count: u64,
+    patterns: &PatternSet,
     stats: &SearchStats,
-) -> Option<(String, Pubkey)> {
-    if stats.found.load(Ordering::Relaxed) {
-        return None;
-    }
-
-    debug!("Starting batch search with {} addresses", BATCH_SIZE);
-    let mut seeds = Vec::with_capacity(BATCH_SIZE);
-    let mut addresses = Vec::with_capacity(BATCH_SIZE);
-
-    // Generate batch of random seeds
-    for _ in 0..BATCH_SIZE {
-        let mut rng = rand::thread_rng();
-        let seed: String = (0..32)
-            .map(|_| {
-                let idx = rng.gen_range(0..CHARSET.len());
-                CHARSET[idx] as char
-            })
-            .collect();
-        seeds.push(seed);
-    }
-    debug!("Generated {} random seeds", seeds.len());
-
-    // Create addresses in parallel
-    addresses.par_extend(seeds.par_iter().filter_map(|seed| {
-        match Pubkey::create_with_seed(
-            &base_keypair.pubkey(),
-            seed,
-            &TOKEN_PROGRAM_ID,
-        ) {
-            Ok(address) => Some((seed.clone(), address)),
-            Err(e) => {
-                debug!("Error creating address with seed {}: {}", seed, e);
-                None
+) -> Option<BatchMatch> {
+    debug!("Starting batch search with {} seeds", count);
+    let mut base_state = Sha256::new();
+    base_state.update(base_pubkey.as_ref());
+
+    // The lowest matching index wins, so a search from a master seed is reproducible
+    let result = (0..count.div_ceil(CHUNK_SIZE as u64))
+        .into_par_iter()
+        .find_map_first(|chunk| {
+            let chunk_start = start + chunk * CHUNK_SIZE as u64;
+            let chunk_len = (CHUNK_SIZE as u64).min(start + count - chunk_start);
+            let mut cursor = schedule.cursor(chunk_start);
+            let mut encoded = [0u8; MAX_ENCODED_LEN];
+            let mut best = ChunkBest::new(stats);
+
+            for offset in 0..chunk_len {
+                let mut seed_state = base_state.clone();
+                seed_state.update(cursor.seed());
+
+                // Owners in order, so the lowest (index, owner) wins
+                for (owner, program_id) in owners.iter().enumerate() {
+                    let mut hasher = seed_state.clone();
+                    hasher.update(program_id.as_ref());
+                    let address: [u8; 32] = hasher.finalize().into();
+
+                    if let Some(pattern) = patterns.find_match_address(&address, &mut encoded) {
+                        return Some(BatchMatch {
+                            attempt_index: chunk_start + offset,
+                            owner,
+                            address: Pubkey::new_from_array(address),
+                            pattern,
+                        });
+                    }
+
+                    best.offer(patterns, &address, chunk_start + offset, owner);
+                }
+                cursor.advance();
             }
-        }
-    }));
-    debug!("Created {} addresses", addresses.len());
 
-    stats.attempts.fetch_add(BATCH_SIZE as u64, Ordering::Relaxed);
+            best.finish();
+            None
+        });
 
-    // Check for matches
-    for (seed, address) in addresses {
-        if matches_pattern(
-            &address.to_string(),
-            pattern,
-            case_insensitive,
-            position,
-        ) {
-            debug!("Found matching address: {}", address);
-            stats.found.store(true, Ordering::Relaxed);
-            return Some((seed, address));
+    let owner_count = owners.len() as u64;
+    match &result {
+        Some(found) => {
+            debug!("Found matching address: {} (pattern #{})", found.address, found.pattern);
+            stats.attempts.fetch_add(
+                (found.attempt_index - start) * owner_count + found.owner as u64 + 1,
+                Ordering::Relaxed,
+            );
+        }
+        None => {
+            stats.attempts.fetch_add(count * owner_count, Ordering::Relaxed);
         }
     }
+    result
+}
+
+/// Measures the CPU search speed of `mode` for `patterns` in attempts, or keys,
+/// per second by grinding for roughly `duration` on a dedicated thread pool.
+pub fn measure_rate(
+    patterns: &[CompoundPattern],
+    mode: SearchMode,
+    threads: Option<usize>,
+    duration: Duration,
+) -> Result<f64> {
+    let patterns = PatternSet::new(patterns)?;
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads.unwrap_or_else(num_cpus::get))
+        .build()?;
+    let base = SearchBase::from_keypair(Arc::new(Keypair::new()));
+    let schedule = SeedSchedule::random(&SeedFormat::default())?;
+    let options = SearchOptions {
+        mode,
+        // The rate hardly depends on the program, fixed seeds or mint
+        pda: (mode == SearchMode::Pda).then(PdaSeeds::default),
+        ata_mint: (mode == SearchMode::Ata).then(Pubkey::default),
+        ..SearchOptions::default()
+    };
+    let grinder = Grinder::new(&options, &base, &schedule, &[OwnerProgram::Token2022])?;
+    let stats = SearchStats::new();
+    let start_time = Instant::now();
+
+    pool.install(|| {
+        let mut next_index = 0u64;
+        while start_time.elapsed() < duration {
+            // A lucky match while measuring doesn't matter, keep going after it
+            next_index = match grinder.search_batch(next_index, grinder.batch_size(), &patterns, &stats) {
+                Some(found) => found.attempt_index + 1,
+                None => next_index + grinder.batch_size(),
+            };
+        }
+    });
 
-    None
+    Ok(stats.attempts.load(Ordering::Relaxed) as f64 / start_time.elapsed().as_secs_f64())
 }
 
 pub async fn find_vanity_address(
@@ -129,133 +688,611 @@ pub async fn find_vanity_address(
     use_gpu: bool,
     threads: Option<usize>,
 ) -> Result<VanityAddressResult> {
-    if position != "start" && position != "end" {
-        return Err(anyhow!("Position must be either 'start' or 'end'"));
+    let result = find_vanity_address_multi(
+        &[PatternSpec::new(pattern, position.parse()?, case_insensitive).into()],
+        use_gpu,
+        threads,
+    )
+    .await?;
+
+    // Print machine-readable output first
+    println!("RESULT_START");
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&serde_json::json!({
+            "base_pubkey": result.base_pubkey,
+            "seed": result.seed,
+            "token_address": result.token_address,
+            "time_taken": result.time_taken,
+            "attempts": result.attempts,
+        }))?
+    );
+    println!("RESULT_END");
+
+    // Save the keypair in Solana CLI format
+    if let Some(keypair_json) = &result.keypair_json {
+        std::fs::create_dir_all("token_keys")?;
+        std::fs::write("token_keys/token_keypair.json", keypair_json)?;
     }
 
-    info!("Starting vanity address search");
-    debug!("Pattern: {}, Position: {}, Case sensitive: {}", pattern, position, !case_insensitive);
+    Ok(result)
+}
 
-    let base_keypair = Keypair::new();
-    let stats = SearchStats::new();
-    let start_time = Instant::now();
-    let num_threads = threads.unwrap_or_else(num_cpus::get);
+/// Searches for an address matching any of `patterns` in a single grinding pass.
+/// `VanityAddressResult::matched_pattern` is the index of the pattern that was hit.
+pub async fn find_vanity_address_multi(
+    patterns: &[CompoundPattern],
+    use_gpu: bool,
+    threads: Option<usize>,
+) -> Result<VanityAddressResult> {
+    find_vanity_address_with_options(
+        patterns,
+        &SearchOptions {
+            use_gpu,
+            threads,
+            ..Default::default()
+        },
+    )
+    .await?
+    .into_found()
+}
 
-    // Initialize Metal device if GPU feature is enabled and requested
-    #[cfg(feature = "gpu")]
-    let metal_device = if use_gpu {
-        match MetalDevice::new() {
-            Ok(device) => {
-                info!("Metal GPU acceleration enabled");
-                Some(device)
+/// Searches for an address matching any of `patterns`, configured by `options`.
+/// Without a cancel handle or budget the search only ends on a match.
+pub async fn find_vanity_address_with_options(
+    patterns: &[CompoundPattern],
+    options: &SearchOptions,
+) -> Result<SearchOutcome> {
+    VanitySearcher::for_options(options)?.find(patterns, options).await
+}
+
+/// Keeps searching until `count` distinct addresses match any of `patterns`, all
+/// derived from the same base key. Each match goes to `options.on_match` as
+/// soon as it is found. A cancel handle or budget can end the search early with
+/// the matches collected so far.
+pub async fn collect_vanity_addresses(
+    patterns: &[CompoundPattern],
+    count: usize,
+    options: &SearchOptions,
+) -> Result<MatchCollection> {
+    VanitySearcher::for_options(options)?.collect(patterns, count, options).await
+}
+
+/// Like `collect_vanity_addresses`, but streams the progress and matches of the
+/// search as they come. Dropping the stream cancels the search.
+pub fn stream_vanity_addresses(
+    patterns: &[CompoundPattern],
+    count: usize,
+    options: &SearchOptions,
+) -> Result<SearchStream> {
+    VanitySearcher::for_options(options)?.stream(patterns, count, options)
+}
+
+/// Searches until `options` runs out of budget or is cancelled and returns the
+/// candidate that came closest, e.g. the address ending in "gems" when "gemstone"
+/// at the end is out of reach. A full match ends the search early.
+pub async fn find_longest_match(
+    patterns: &[CompoundPattern],
+    options: &SearchOptions,
+) -> Result<LongestMatch> {
+    // Best effort searches only run on the CPU
+    VanitySearcher::new(options.threads, false)?.find_longest_match(patterns, options).await
+}
+
+/// A search ready to run: its base key and seed schedule, how far earlier runs
+/// got and where to save checkpoints.
+struct SearchStart {
+    base: SearchBase,
+    schedule: SeedSchedule,
+    progress: Progress,
+    checkpoint: Option<CheckpointWriter>,
+}
+
+fn start_search(patterns: &PatternSet, options: &SearchOptions) -> Result<SearchStart> {
+    match options.mode {
+        SearchMode::Seed => {}
+        SearchMode::Keypair | SearchMode::ExpandedKeypair | SearchMode::Ata => {
+            if !matches!(options.base_key, BaseKey::Generate) {
+                return Err(anyhow!("A keypair search generates its own keys and can't be combined with a base key"));
             }
-            Err(e) => {
-                warn!("Failed to initialize Metal GPU: {}", e);
-                warn!("Falling back to CPU");
-                None
+            if options.seed_format != SeedFormat::default() {
+                return Err(anyhow!("A keypair search uses no seeds, so it takes no seed format"));
             }
         }
+        SearchMode::Pda => {
+            if !matches!(options.base_key, BaseKey::Generate) {
+                return Err(anyhow!("A PDA search derives its addresses from the program and can't be combined with a base key"));
+            }
+        }
+    }
+    if options.mode != SearchMode::Seed && options.owner_programs().len() > 1 {
+        return Err(anyhow!("The address of a {} search doesn't depend on its owner, pass at most one", options.mode));
+    }
+    if options.pda.is_some() != (options.mode == SearchMode::Pda) {
+        return Err(anyhow!("PDA seeds go with PDA searches and PDA searches need them"));
+    }
+    if options.ata_mint.is_some() != (options.mode == SearchMode::Ata) {
+        return Err(anyhow!("A mint goes with ATA searches and ATA searches need one"));
+    }
+
+    let (base, schedule) = match (&options.master_seed, &options.base_key) {
+        (Some(master_seed), BaseKey::Generate) => {
+            info!("Deriving base keypair and seeds from the master seed");
+            (
+                SearchBase::from_keypair(Arc::new(base_keypair_from_master_seed(master_seed)?)),
+                SeedSchedule::from_master_seed(master_seed, &options.seed_format)?,
+            )
+        }
+        (Some(_), _) => {
+            return Err(anyhow!(
+                "A master seed derives its own base keypair and can't be combined with a base key"
+            ));
+        }
+        (None, BaseKey::Generate) => (
+            SearchBase::from_keypair(Arc::new(Keypair::new())),
+            SeedSchedule::random(&options.seed_format)?,
+        ),
+        (None, BaseKey::Keypair(keypair)) => {
+            options.seed_format.validate_shared_base()?;
+            (
+                SearchBase::from_keypair(keypair.clone()),
+                SeedSchedule::random(&options.seed_format)?,
+            )
+        }
+        (None, BaseKey::Pubkey(pubkey)) => {
+            info!("Searching on base pubkey {} only, results carry no keypair", pubkey);
+            options.seed_format.validate_shared_base()?;
+            (
+                SearchBase::from_pubkey(*pubkey),
+                SeedSchedule::random(&options.seed_format)?,
+            )
+        }
+    };
+
+    // A PDA search has no base key, the program takes its place
+    let base = match &options.pda {
+        Some(pda) => SearchBase::from_pubkey(pda.program_id),
+        None => base,
+    };
+
+    let checkpoint = options
+        .checkpoint
+        .as_ref()
+        .map(|checkpoint| {
+            CheckpointWriter::new(checkpoint, options, patterns.patterns(), &base, &schedule)
+        })
+        .transpose()?;
+
+    Ok(SearchStart {
+        base,
+        schedule,
+        progress: Progress::default(),
+        checkpoint,
+    })
+}
+
+/// Continues the search saved in `checkpoint`, skipping every seed it already
+/// covered, until it has the matches it still had to find. `options.checkpoint`
+/// supplies where to keep saving and the passphrase, which checkpoints of
+/// pubkey-only searches don't need.
+pub async fn resume_vanity_address(
+    checkpoint: Checkpoint,
+    options: &SearchOptions,
+) -> Result<MatchCollection> {
+    VanitySearcher::for_options(options)?.resume(checkpoint, options).await
+}
+
+fn partial_match(
+    best: BestCandidate,
+    grinder: &Grinder,
+    owners: &[OwnerProgram],
+    patterns: &PatternSet,
+) -> PartialMatch {
+    PartialMatch {
+        seed: grinder.seed(best.attempt_index),
+        token_address: best.address.to_string(),
+        owner: owners[best.owner],
+        attempt_index: best.attempt_index,
+        matched_pattern: best.compound,
+        matched_chars: best.score,
+        pattern_chars: patterns.pattern_chars(best.compound),
+    }
+}
+
+/// How far a search got before the current run.
+#[derive(Debug, Default)]
+struct Progress {
+    next_index: u64,
+    attempts: u64,
+    elapsed: Duration,
+}
+
+/// Runs `start` on `searcher` until it has `match_count` matches or `options` stops it.
+fn run_search(
+    searcher: &VanitySearcher,
+    patterns: &PatternSet,
+    match_count: usize,
+    options: &SearchOptions,
+    start: &mut SearchStart,
+) -> Result<MatchCollection> {
+    let SearchStart {
+        base,
+        schedule,
+        progress,
+        checkpoint,
+    } = start;
+
+    info!("Starting vanity address search");
+    for compound in patterns.patterns() {
+        debug!("Pattern: {}", compound);
+    }
+    let difficulty = patterns.difficulty();
+    info!("Difficulty: 1 in {:.0} addresses", difficulty.expected_attempts());
+
+    let owners = options.owner_programs();
+    for owner in &owners {
+        owner.validate()?;
+    }
+    let grinder = Grinder::new(options, base, schedule, &owners)?;
+    info!(
+        "Owner programs: {}",
+        owners.iter().map(ToString::to_string).collect::<Vec<String>>().join(", ")
+    );
+
+    let base_pubkey = *base.pubkey();
+    let stats = if options.track_best {
+        SearchStats::tracking_best()
+    } else {
+        SearchStats::new()
+    };
+    stats.attempts.store(progress.attempts, Ordering::Relaxed);
+    let start_time = Instant::now();
+
+    // Use the searcher's Metal device if the search allows it
+    #[cfg(feature = "gpu")]
+    let metal_device = if options.use_gpu && options.mode != SearchMode::Seed {
+        warn!("GPU search only grinds seed addresses");
+        warn!("Falling back to CPU");
+        None
+    } else if options.use_gpu
+        && (options.master_seed.is_some()
+            || options.checkpoint.is_some()
+            || options.seed_format != SeedFormat::default())
+    {
+        warn!("GPU search doesn't follow the seed schedule master seeds, checkpoints and seed formats rely on");
+        warn!("Falling back to CPU");
+        None
+    } else if options.use_gpu && owners != [OwnerProgram::Token2022] {
+        warn!("GPU search only grinds Token-2022 addresses");
+        warn!("Falling back to CPU");
+        None
+    } else if options.use_gpu && match_count > 1 {
+        warn!("GPU search can't collect more than one match");
+        warn!("Falling back to CPU");
+        None
+    } else if options.use_gpu && patterns.gpu_pattern().is_none() {
+        warn!("GPU search supports a single 'start' or 'end' pattern only");
+        warn!("Falling back to CPU");
+        None
+    } else if options.use_gpu && searcher.metal_device().is_none() {
+        warn!("The searcher has no GPU");
+        warn!("Falling back to CPU");
+        None
+    } else if options.use_gpu {
+        searcher.metal_device()
     } else {
         None
     };
 
+    // Searchers built without GPU support never have a device
     #[cfg(not(feature = "gpu"))]
-    let metal_device: Option<MetalDevice> = None;
-
-    if metal_device.is_none() {
-        info!("Using {} CPU threads", num_threads);
-        rayon::ThreadPoolBuilder::new()
-            .num_threads(num_threads)
-            .build_global()?;
-    }
-
-    let stats_clone = stats.clone();
-    let attempts_clone = stats.attempts.clone();
-
-    // Progress reporting thread
-    std::thread::spawn(move || {
-        let mut last_attempts = 0u64;
-        let mut last_time = Instant::now();
-
-        while !stats_clone.found.load(Ordering::Relaxed) {
-            std::thread::sleep(std::time::Duration::from_secs(1));
-            let current_attempts = attempts_clone.load(Ordering::Relaxed);
-            let attempts_delta = current_attempts - last_attempts;
-            let time_delta = last_time.elapsed().as_secs_f64();
-            
-            info!(
-                "Speed: {:.2}M attempts/s, Total: {}M attempts",
-                attempts_delta as f64 / time_delta / 1_000_000.0,
-                current_attempts / 1_000_000
-            );
+    let metal_device = searcher.metal_device();
 
-            last_attempts = current_attempts;
-            last_time = Instant::now();
+    let backend = if metal_device.is_some() {
+        Backend::Gpu
+    } else {
+        info!("Using {} CPU threads", searcher.threads());
+        Backend::Cpu {
+            threads: searcher.threads(),
         }
-    });
+    };
+    let (stop_reporter, reporter_stopped) = mpsc::channel::<()>();
 
-    // Main search loop
-    loop {
-        let result = if let Some(device) = metal_device.as_ref() {
-            debug!("Using GPU for search batch");
-            device.search_batch(
-                &base_keypair,
-                pattern,
-                position,
-                case_insensitive,
-                &stats,
-            )
-        } else {
-            debug!("Using CPU for search batch");
-            search_batch(
-                &base_keypair,
-                pattern,
-                position,
-                case_insensitive,
-                &stats,
-            )
-        };
+    let collection = std::thread::scope(|scope| {
+        if let Some(callback) = &options.on_progress {
+            let interval = options.progress_interval.unwrap_or(PROGRESS_INTERVAL);
+            let (stats, grinder, owners, progress) = (&stats, &grinder, &owners, &*progress);
+            scope.spawn(move || {
+                let mut last_attempts = stats.attempts.load(Ordering::Relaxed);
+                let mut last_time = Instant::now();
 
-        if let Some((seed, address)) = result {
-            let elapsed = start_time.elapsed();
-            let attempts = stats.attempts.load(Ordering::Relaxed);
-            
-            // Print machine-readable output first
-            println!("RESULT_START");
-            println!("{{");
-            println!("  \"base_pubkey\": \"{}\",", base_keypair.pubkey());
-            println!("  \"seed\": \"{}\",", seed);
-            println!("  \"token_address\": \"{}\",", address);
-            println!("  \"time_taken\": {},", elapsed.as_secs_f64());
-            println!("  \"attempts\": {}", attempts);
-            println!("}}");
-            println!("RESULT_END");
-            
-            // Then print human-readable output
-            info!("Found matching address!");
-            info!("Base pubkey: {}", base_keypair.pubkey());
-            info!("Seed: {}", seed);
-            info!("Token address: {}", address);
-            info!("Time taken: {:.2}s", elapsed.as_secs_f64());
-            info!(
-                "Average speed: {:.2}M attempts/s",
-                attempts as f64 / elapsed.as_secs_f64() / 1_000_000.0
-            );
-            
-            // Save the keypair in Solana CLI format
-            std::fs::create_dir_all("token_keys")?;
-            let keypair_bytes = base_keypair.to_bytes();
-            let keypair_str = format!("[{}]", keypair_bytes.iter().map(|b| b.to_string()).collect::<Vec<String>>().join(","));
-            std::fs::write("token_keys/token_keypair.json", &keypair_str)?;
-
-            return Ok(VanityAddressResult {
-                base_pubkey: base_keypair.pubkey().to_string(),
-                seed: seed.clone(),
-                token_address: address.to_string(),
-                keypair_json: keypair_str.clone(),
-                time_taken: elapsed.as_secs_f64(),
-                attempts,
+                // Sleeps between events until the search drops `stop_reporter`, then
+                // reports the final count
+                loop {
+                    let stopped = !matches!(reporter_stopped.recv_timeout(interval), Err(RecvTimeoutError::Timeout));
+                    let attempts = stats.attempts.load(Ordering::Relaxed);
+                    let run_time = start_time.elapsed().as_secs_f64();
+                    let average_rate = (attempts - progress.attempts) as f64 / run_time;
+
+                    callback.call(&ProgressEvent {
+                        backend,
+                        attempts,
+                        elapsed: progress.elapsed.as_secs_f64() + run_time,
+                        rate: (attempts - last_attempts) as f64 / last_time.elapsed().as_secs_f64(),
+                        average_rate,
+                        chance: difficulty.success_probability(attempts),
+                        eta: difficulty.expected_attempts() / average_rate,
+                        best_match: stats.best().map(|best| partial_match(best, grinder, owners, patterns)),
+                    });
+
+                    if stopped {
+                        break;
+                    }
+                    last_attempts = attempts;
+                    last_time = Instant::now();
+                }
             });
         }
+        // Dropped on every way out of the search, which ends the reporter
+        let _stop_reporter = stop_reporter;
+
+        // Main search loop
+        let mut next_index = progress.next_index;
+        let mut matches: Vec<VanityAddressResult> = Vec::new();
+        loop {
+            let elapsed = progress.elapsed + start_time.elapsed();
+            let attempts = stats.attempts.load(Ordering::Relaxed);
+
+            let stop_reason = if options.cancel.as_ref().is_some_and(CancelHandle::is_cancelled) {
+                Some(StopReason::Cancelled)
+            } else if options.timeout.is_some_and(|timeout| start_time.elapsed() >= timeout) {
+                Some(StopReason::Timeout)
+            } else if options.max_attempts.is_some_and(|max| attempts >= max) {
+                Some(StopReason::MaxAttempts)
+            } else if grinder.capacity().is_some_and(|capacity| next_index >= capacity) {
+                Some(StopReason::SeedsExhausted)
+            } else {
+                None
+            };
+
+            if let Some(reason) = stop_reason {
+                if matches.is_empty() {
+                    info!("Stopping without a match: {}", reason);
+                } else {
+                    info!("Stopping after {} of {} matches: {}", matches.len(), match_count, reason);
+                }
+                if let Some(writer) = checkpoint.as_mut() {
+                    writer.save(next_index, match_count - matches.len(), attempts, elapsed.as_secs_f64())?;
+                    info!("Progress saved, resume the search from the checkpoint");
+                }
+
+                return Ok(MatchCollection {
+                    matches,
+                    incomplete: Some(SearchSummary {
+                        reason,
+                        attempts,
+                        time_taken: elapsed.as_secs_f64(),
+                        best_match: stats.best().map(|best| partial_match(best, &grinder, &owners, patterns)),
+                    }),
+                });
+            }
+
+            if let Some(writer) = checkpoint.as_mut().filter(|writer| writer.due()) {
+                writer.save(next_index, match_count - matches.len(), attempts, elapsed.as_secs_f64())?;
+                debug!("Checkpoint saved at attempt {}", next_index);
+            }
+
+            let result = if let Some(device) = metal_device.as_ref() {
+                debug!("Using GPU for search batch");
+                let spec = patterns.gpu_pattern().expect("GPU search requires a single pattern");
+                device
+                    .search_batch(
+                        &base_pubkey,
+                        &spec.pattern,
+                        &spec.position.to_string(),
+                        spec.case_insensitive,
+                        &stats,
+                    )
+                    .map(|(seed, address)| (seed, OwnerProgram::Token2022, address, 0, None))
+            } else {
+                debug!("Using CPU for search batch");
+                // Stop at the attempt budget, which counts addresses, and at the last index
+                let batch_size = grinder.batch_size();
+                let count = options
+                    .max_attempts
+                    .map_or(batch_size, |max| {
+                        batch_size.min((max - attempts).div_ceil(grinder.addresses_per_index()))
+                    })
+                    .min(grinder.capacity().map_or(u64::MAX, |capacity| capacity - next_index));
+                let result = searcher.pool().install(|| grinder.search_batch(next_index, count, patterns, &stats));
+                // Pick up at the next index after a match, the rest of the batch may hold more
+                next_index = result.map_or(next_index + count, |found| found.attempt_index + 1);
+                result.map(|found| {
+                    (
+                        grinder.seed(found.attempt_index),
+                        owners[found.owner],
+                        found.address,
+                        found.pattern,
+                        Some(found.attempt_index),
+                    )
+                })
+            };
+
+            if let Some((seed, owner, address, matched_pattern, attempt_index)) = result {
+                let elapsed = progress.elapsed + start_time.elapsed();
+                let attempts = stats.attempts.load(Ordering::Relaxed);
+
+                info!("Found matching address! ({} of {})", matches.len() + 1, match_count);
+                info!("Matched pattern: {}", patterns.patterns()[matched_pattern]);
+                if options.mode == SearchMode::Seed {
+                    info!("Base pubkey: {}", base_pubkey);
+                    info!("Seed: {}", seed);
+                }
+                if let Some(index) = attempt_index {
+                    info!("Attempt index: {}", index);
+                }
+                info!("Token address: {}", address);
+                info!("Owner: {}", owner);
+                info!("Time taken: {:.2}s", elapsed.as_secs_f64());
+                info!(
+                    "Average speed: {:.2}M attempts/s",
+                    attempts as f64 / elapsed.as_secs_f64() / 1_000_000.0
+                );
+
+                let (key_pubkey, keypair_json) = grinder.keys(attempt_index);
+                let result = VanityAddressResult {
+                    mode: options.mode,
+                    base_pubkey: key_pubkey.to_string(),
+                    seed,
+                    token_address: address.to_string(),
+                    owner,
+                    keypair_json,
+                    time_taken: elapsed.as_secs_f64(),
+                    attempts,
+                    matched_pattern,
+                    attempt_index,
+                    pda: grinder.pda(attempt_index),
+                    mint: grinder.mint(),
+                };
+                if let Some(callback) = &options.on_match {
+                    callback.call(&result);
+                }
+                matches.push(result);
+
+                if matches.len() == match_count {
+                    if let Some(writer) = checkpoint.take() {
+                        writer.remove()?;
+                        info!("Search complete, checkpoint removed");
+                    }
+                    return Ok(MatchCollection {
+                        matches,
+                        incomplete: None,
+                    });
+                }
+            }
+        }
+    });
+
+    searcher.record_search(stats.attempts.load(Ordering::Relaxed) - progress.attempts);
+    collection
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use owner::TOKEN_2022_PROGRAM_ID;
+
+    #[test]
+    fn test_search_batch_matches_create_with_seed() {
+        let patterns = PatternSet::new(&[PatternSpec::new("?", Position::End, false).into()]).unwrap();
+        let base_pubkey = Pubkey::new_unique();
+        let schedule = SeedSchedule::random(&SeedFormat::default()).unwrap();
+
+        let found =
+            search_batch(&base_pubkey, &[TOKEN_2022_PROGRAM_ID], &schedule, 5, BATCH_SIZE, &patterns, &SearchStats::new()).unwrap();
+        assert_eq!((found.attempt_index, found.owner, found.pattern), (5, 0, 0));
+        let seed = schedule.cursor(found.attempt_index).to_string();
+        assert_eq!(Pubkey::create_with_seed(&base_pubkey, &seed, &TOKEN_2022_PROGRAM_ID).unwrap(), found.address);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_search_batch_tries_every_owner() {
+        let patterns = PatternSet::new(&[PatternSpec::new("ab", Position::End, false).into()]).unwrap();
+        let base_pubkey = Pubkey::new_unique();
+        let schedule = SeedSchedule::random(&SeedFormat::default()).unwrap();
+        let owners = [OwnerProgram::SplToken.program_id(), TOKEN_2022_PROGRAM_ID];
+        let stats = SearchStats::new();
+
+        let found = search_batch(&base_pubkey, &owners, &schedule, 0, BATCH_SIZE, &patterns, &stats).unwrap();
+        let seed = schedule.cursor(found.attempt_index).to_string();
+        assert_eq!(Pubkey::create_with_seed(&base_pubkey, &seed, &owners[found.owner]).unwrap(), found.address);
+        assert!(found.address.to_string().ends_with("ab"));
+        assert_eq!(
+            stats.attempts.load(Ordering::Relaxed),
+            found.attempt_index * 2 + found.owner as u64 + 1
+        );
+
+        // No address of an earlier seed, or of an earlier owner of the same seed, matches
+        for index in 0..=found.attempt_index {
+            let seed = schedule.cursor(index).to_string();
+            for (owner, program_id) in owners.iter().enumerate() {
+                if (index, owner) < (found.attempt_index, found.owner) {
+                    let address = Pubkey::create_with_seed(&base_pubkey, &seed, program_id).unwrap();
+                    assert!(!address.to_string().ends_with("ab"));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_master_seed_reproduces_lowest_match() {
+        let master_seed = [7u8; 32];
+        let patterns = PatternSet::new(&[PatternSpec::new("ab", Position::End, false).into()]).unwrap();
+        let base_pubkey = base_keypair_from_master_seed(&master_seed).unwrap().pubkey();
+        let schedule = SeedSchedule::from_master_seed(&master_seed, &SeedFormat::default()).unwrap();
+
+        let found =
+            search_batch(&base_pubkey, &[TOKEN_2022_PROGRAM_ID], &schedule, 0, BATCH_SIZE, &patterns, &SearchStats::new()).unwrap();
+        let (attempt_index, address) = (found.attempt_index, found.address);
+        let candidate = reproduce_candidate(&master_seed, &SeedFormat::default(), &OwnerProgram::default(), attempt_index).unwrap();
+        assert_eq!(candidate.token_address, address);
+        assert!(address.to_string().ends_with("ab"));
+
+        for earlier in 0..attempt_index {
+            let candidate = reproduce_candidate(&master_seed, &SeedFormat::default(), &OwnerProgram::default(), earlier).unwrap();
+            assert!(!candidate.token_address.to_string().ends_with("ab"));
+        }
+    }
+
+    #[test]
+    fn test_search_batch_resumes_after_match() {
+        let patterns = PatternSet::new(&[PatternSpec::new("a", Position::End, false).into()]).unwrap();
+        let base_pubkey = Pubkey::new_unique();
+        let schedule = SeedSchedule::random(&SeedFormat::default()).unwrap();
+        let stats = SearchStats::new();
+
+        // Every match of a range shows up once when continuing right after the last one
+        let mut next_index = 0;
+        let mut found = Vec::new();
+        while let Some(batch_match) = search_batch(&base_pubkey, &[TOKEN_2022_PROGRAM_ID], &schedule, next_index, 2000 - next_index, &patterns, &stats) {
+            found.push(batch_match.attempt_index);
+            assert!(batch_match.address.to_string().ends_with('a'));
+            next_index = batch_match.attempt_index + 1;
+        }
+        assert_eq!(stats.attempts.load(Ordering::Relaxed), 2000);
+
+        let expected: Vec<u64> = (0..2000)
+            .filter(|&i| {
+                let seed = schedule.cursor(i).to_string();
+                Pubkey::create_with_seed(&base_pubkey, &seed, &TOKEN_2022_PROGRAM_ID)
+                    .unwrap()
+                    .to_string()
+                    .ends_with('a')
+            })
+            .collect();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_search_batch_honors_count_and_tracks_best() {
+        let patterns = PatternSet::new(&[PatternSpec::new("zzzzzzzz", Position::End, false).into()]).unwrap();
+        let base_pubkey = Pubkey::new_unique();
+        let schedule = SeedSchedule::random(&SeedFormat::default()).unwrap();
+        let stats = SearchStats::tracking_best();
+
+        assert!(search_batch(&base_pubkey, &[TOKEN_2022_PROGRAM_ID], &schedule, 0, 10_000, &patterns, &stats).is_none());
+        assert_eq!(stats.attempts.load(Ordering::Relaxed), 10_000);
+
+        // Searches that don't ask for it skip scoring their misses
+        let untracked = SearchStats::new();
+        assert!(search_batch(&base_pubkey, &[TOKEN_2022_PROGRAM_ID], &schedule, 0, 10_000, &patterns, &untracked).is_none());
+        assert!(untracked.best().is_none());
+
+        let best = stats.best().unwrap();
+        assert!(best.attempt_index < 10_000);
+        let seed = schedule.cursor(best.attempt_index).to_string();
+        let address = Pubkey::create_with_seed(&base_pubkey, &seed, &TOKEN_2022_PROGRAM_ID).unwrap();
+        assert_eq!(address, best.address);
+        assert!(address.to_string().ends_with(&"z".repeat(best.score)));
+    }
+}
@@ -1,39 +1,47 @@
 use anyhow::{anyhow, Result};
-use clap::Parser;
-use log::{info, warn, debug};
-use rayon::prelude::*;
+use clap::{Parser, Subcommand};
+use log::{info, debug, error};
+use serde::Serialize;
 use solana_program::pubkey::Pubkey;
-use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::signature::{read_keypair, read_keypair_file, Signer};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
 use std::{
-    sync::{
-        atomic::{AtomicBool, AtomicU64, Ordering},
-        Arc,
-    },
-    time::Instant,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+use token22_vanity::{
+    collect_vanity_addresses, BaseKey, estimate_difficulty, find_longest_match, measure_rate, reproduce_candidate,
+    reproduce_ata_wallet, reproduce_expanded_keypair, reproduce_keypair, reproduce_pda, resume_vanity_address, run_benchmark, CancelHandle, Checkpoint, CheckpointOptions,
+    CompoundPattern, ExpandedKeypair, MatchCallback, MatchCollection, OwnerProgram, PatternSpec, Position, ProgressCallback,
+    PdaSeeds, ProgressEvent, SearchMode, SearchOptions, SearchSummary, SeedAlphabet, SeedFormat,
+    VanityAddressResult,
 };
-use rand::Rng;
-use serde_json;
-use token22_vanity::VanityAddressResult;
-
-mod metal;
-use metal::MetalDevice;
-
-const CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-    /// Pattern to search for
-    #[arg(short, long)]
-    pattern: String,
+    #[command(subcommand)]
+    command: Option<Command>,
 
-    /// Position of pattern (start/end)
-    #[arg(short, long)]
-    position: String,
+    #[command(flatten)]
+    patterns: PatternArgs,
 
-    /// Case insensitive search
-    #[arg(short, long)]
-    case_insensitive: bool,
+    /// Kind of address to grind: seed (create_with_seed addresses of a base key),
+    /// keypair (mint keypairs), expanded-keypair (faster mint keys only Rust signers
+    /// can use), pda (program derived addresses, see --program-id) or ata (wallets
+    /// whose associated token account for --mint matches)
+    #[arg(long, default_value_t = SearchMode::Seed)]
+    mode: SearchMode,
+
+    #[command(flatten)]
+    pda: PdaArgs,
+
+    /// Mint whose associated token accounts an ATA search grinds wallets for
+    #[arg(long)]
+    mint: Option<Pubkey>,
 
     /// Number of CPU threads (default: num_cpus)
     #[arg(short, long)]
@@ -42,276 +50,782 @@ struct Args {
     /// Use GPU acceleration if available
     #[arg(short, long)]
     gpu: bool,
+
+    /// Number of distinct matches to collect, all from the same base keypair
+    #[arg(long, default_value_t = 1)]
+    count: usize,
+
+    /// Return the closest candidate when the budget runs out or the search is stopped
+    #[arg(long, conflicts_with = "count")]
+    best_effort: bool,
+
+    /// Hex-encoded 32-byte secret to derive the base keypair and seeds from, making the search reproducible
+    #[arg(long, value_parser = parse_master_seed)]
+    master_seed: Option<[u8; 32]>,
+
+    /// Keypair file to use as the base key instead of a fresh keypair
+    #[arg(long, conflicts_with_all = ["master_seed", "base_pubkey"])]
+    base_keypair: Option<PathBuf>,
+
+    /// Base public key to grind seeds for without any secret key; only seeds are reported
+    #[arg(long, conflicts_with = "master_seed")]
+    base_pubkey: Option<Pubkey>,
+
+    #[command(flatten)]
+    seed_format: SeedArgs,
+
+    /// Program owning the address: spl-token, token-2022 or a program id. Repeat to
+    /// accept an address owned by any of several (default: token-2022)
+    #[arg(long)]
+    owner: Vec<OwnerProgram>,
+
+    /// Save the search progress to this file so it can be resumed
+    #[arg(long)]
+    checkpoint: Option<PathBuf>,
+
+    /// Seconds between checkpoint saves
+    #[arg(long, default_value_t = 60)]
+    checkpoint_interval: u64,
+
+    #[command(flatten)]
+    budget: BudgetArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Estimate how long a search will take on this machine
+    Estimate {
+        #[command(flatten)]
+        patterns: PatternArgs,
+
+        /// Search speed in attempts/s (measured on this machine if omitted)
+        #[arg(long)]
+        rate: Option<f64>,
+
+        /// Kind of address to measure the search speed for (seed/keypair/expanded-keypair/pda/ata)
+        #[arg(long, default_value_t = SearchMode::Seed)]
+        mode: SearchMode,
+
+        /// Number of CPU threads used to measure the speed (default: num_cpus)
+        #[arg(short, long)]
+        threads: Option<usize>,
+    },
+    /// Compare the CPU search speed against the original allocating search loop
+    Bench {
+        /// Seconds to run each search loop for
+        #[arg(long, default_value_t = 5)]
+        seconds: u64,
+
+        /// Number of CPU threads (default: num_cpus)
+        #[arg(short, long)]
+        threads: Option<usize>,
+    },
+    /// Regenerate the candidate a master seed search tried at the given attempt index
+    Reproduce {
+        /// Hex-encoded 32-byte master seed of the search
+        #[arg(long, value_parser = parse_master_seed)]
+        master_seed: [u8; 32],
+
+        /// Attempt index reported by the search
+        #[arg(long)]
+        attempt_index: u64,
+
+        /// Kind of address the search ground (seed/keypair/expanded-keypair/pda/ata)
+        #[arg(long, default_value_t = SearchMode::Seed)]
+        mode: SearchMode,
+
+        #[command(flatten)]
+        seed_format: SeedArgs,
+
+        #[command(flatten)]
+        pda: PdaArgs,
+
+        /// Mint of an ATA search
+        #[arg(long)]
+        mint: Option<Pubkey>,
+
+        /// Program owning the address: spl-token, token-2022 or a program id
+        #[arg(long, default_value_t = OwnerProgram::Token2022)]
+        owner: OwnerProgram,
+    },
+    /// Continue a search from its checkpoint
+    Resume {
+        /// Checkpoint file written by a search started with --checkpoint
+        checkpoint: PathBuf,
+
+        /// Number of CPU threads (default: num_cpus)
+        #[arg(short, long)]
+        threads: Option<usize>,
+
+        /// Seconds between checkpoint saves
+        #[arg(long, default_value_t = 60)]
+        checkpoint_interval: u64,
+
+        #[command(flatten)]
+        budget: BudgetArgs,
+    },
 }
 
-const TOKEN_PROGRAM_ID: Pubkey = solana_program::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
-const BATCH_SIZE: usize = 1_000_000;
+#[derive(clap::Args, Debug)]
+struct BudgetArgs {
+    /// Give up after this many seconds
+    #[arg(long)]
+    timeout: Option<u64>,
 
-#[derive(Clone)]
-struct SearchStats {
-    attempts: Arc<AtomicU64>,
-    found: Arc<AtomicBool>,
+    /// Give up after this many attempts
+    #[arg(long)]
+    max_attempts: Option<u64>,
 }
 
-impl SearchStats {
-    fn new() -> Self {
-        Self {
-            attempts: Arc::new(AtomicU64::new(0)),
-            found: Arc::new(AtomicBool::new(false)),
+#[derive(clap::Args, Debug)]
+struct SeedArgs {
+    /// Length of the generated seeds in bytes
+    #[arg(long, default_value_t = 32)]
+    seed_len: usize,
+
+    /// Characters of the generated seeds (alnum/hex/base58)
+    #[arg(long, default_value_t = SeedAlphabet::Alnum)]
+    seed_alphabet: SeedAlphabet,
+
+    /// Fixed start of every seed, e.g. "texaglo-"
+    #[arg(long, default_value = "")]
+    seed_prefix: String,
+}
+
+impl SeedArgs {
+    fn to_format(&self) -> Result<SeedFormat> {
+        let format = SeedFormat {
+            len: self.seed_len,
+            alphabet: self.seed_alphabet,
+            prefix: self.seed_prefix.clone(),
+        };
+        format.validate()?;
+        Ok(format)
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct PdaArgs {
+    /// Program deriving the addresses of a PDA search
+    #[arg(long)]
+    program_id: Option<Pubkey>,
+
+    /// Fixed seed of a PDA search as text, hex:<bytes> or pubkey:<base58>. Repeat
+    /// in the order the program uses them
+    #[arg(long)]
+    pda_seed: Vec<String>,
+
+    /// Place of the ground seed among the fixed seeds (default: last)
+    #[arg(long)]
+    pda_seed_position: Option<usize>,
+}
+
+impl PdaArgs {
+    fn to_seeds(&self, mode: SearchMode) -> Result<Option<PdaSeeds>> {
+        if mode != SearchMode::Pda {
+            if self.program_id.is_some() || !self.pda_seed.is_empty() || self.pda_seed_position.is_some() {
+                return Err(anyhow!("--program-id and the PDA seeds only apply to --mode pda"));
+            }
+            return Ok(None);
         }
+
+        let seeds = self.pda_seed.iter().map(|seed| parse_pda_seed(seed)).collect::<Result<Vec<_>>>()?;
+        let pda = PdaSeeds {
+            program_id: self.program_id.ok_or_else(|| anyhow!("A PDA search needs --program-id"))?,
+            variable_position: self.pda_seed_position.unwrap_or(seeds.len()),
+            seeds,
+        };
+        pda.validate()?;
+        Ok(Some(pda))
     }
 }
 
-fn matches_pattern(address: &str, pattern: &str, case_insensitive: bool, position: &str) -> bool {
-    let (address, pattern) = if case_insensitive {
-        (address.to_lowercase(), pattern.to_lowercase())
+fn parse_pda_seed(s: &str) -> Result<Vec<u8>> {
+    if let Some(hex) = s.strip_prefix("hex:") {
+        Ok(hex::decode(hex).map_err(|e| anyhow!("Invalid hex seed '{}': {}", s, e))?)
+    } else if let Some(pubkey) = s.strip_prefix("pubkey:") {
+        Ok(Pubkey::from_str(pubkey)
+            .map_err(|e| anyhow!("Invalid pubkey seed '{}': {}", s, e))?
+            .to_bytes()
+            .to_vec())
     } else {
-        (address.to_string(), pattern.to_string())
-    };
+        Ok(s.as_bytes().to_vec())
+    }
+}
 
-    match position {
-        "start" => address.starts_with(&pattern),
-        "end" => address.ends_with(&pattern),
-        _ => false,
+impl BudgetArgs {
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout.map(Duration::from_secs)
     }
 }
 
-fn search_batch(
-    base_keypair: &Keypair,
-    pattern: &str,
-    position: &str,
+fn parse_master_seed(s: &str) -> Result<[u8; 32], String> {
+    let bytes = hex::decode(s).map_err(|e| format!("invalid hex: {}", e))?;
+    bytes
+        .try_into()
+        .map_err(|bytes: Vec<u8>| format!("expected 32 bytes, got {}", bytes.len()))
+}
+
+#[derive(clap::Args, Debug)]
+struct PatternArgs {
+    /// Pattern to search for (repeat to search for several patterns at once)
+    #[arg(short, long, required_unless_present_any = ["prefix", "suffix"])]
+    pattern: Vec<String>,
+
+    /// Position of pattern (start/end/contains/at:<offset>), either once for all patterns or once per pattern
+    #[arg(long)]
+    position: Vec<Position>,
+
+    /// Case insensitive search
+    #[arg(short, long)]
     case_insensitive: bool,
-    stats: &SearchStats,
-) -> Option<(String, Pubkey)> {
-    if stats.found.load(Ordering::Relaxed) {
-        return None;
-    }
 
-    debug!("Starting batch search with {} addresses", BATCH_SIZE);
-    let mut seeds = Vec::with_capacity(BATCH_SIZE);
-    let mut addresses = Vec::with_capacity(BATCH_SIZE);
-
-    // Generate batch of random seeds
-    for _ in 0..BATCH_SIZE {
-        let mut rng = rand::thread_rng();
-        let seed: String = (0..32)
-            .map(|_| {
-                let idx = rng.gen_range(0..CHARSET.len());
-                CHARSET[idx] as char
+    /// Start pattern that must hold together with --suffix
+    #[arg(long)]
+    prefix: Option<String>,
+
+    /// Case insensitive matching for --prefix
+    #[arg(long)]
+    prefix_case_insensitive: bool,
+
+    /// End pattern that must hold together with --prefix
+    #[arg(long)]
+    suffix: Option<String>,
+
+    /// Case insensitive matching for --suffix
+    #[arg(long)]
+    suffix_case_insensitive: bool,
+}
+
+impl PatternArgs {
+    fn to_patterns(&self) -> Result<Vec<CompoundPattern>> {
+        if !self.pattern.is_empty()
+            && self.position.len() != 1
+            && self.position.len() != self.pattern.len()
+        {
+            return Err(anyhow!(
+                "Expected 1 or {} positions, got {}",
+                self.pattern.len(),
+                self.position.len()
+            ));
+        }
+
+        let mut patterns: Vec<CompoundPattern> = self
+            .pattern
+            .iter()
+            .enumerate()
+            .map(|(i, pattern)| {
+                let position = self.position.get(i).unwrap_or(&self.position[0]);
+                PatternSpec::new(pattern, *position, self.case_insensitive).into()
             })
             .collect();
-        seeds.push(seed);
-    }
-    debug!("Generated {} random seeds", seeds.len());
-
-    // Create addresses in parallel
-    addresses.par_extend(seeds.par_iter().filter_map(|seed| {
-        match Pubkey::create_with_seed(
-            &base_keypair.pubkey(),
-            seed,
-            &TOKEN_PROGRAM_ID,
-        ) {
-            Ok(address) => Some((seed.clone(), address)),
-            Err(e) => {
-                debug!("Error creating address with seed {}: {}", seed, e);
-                None
-            }
+
+        // --prefix and --suffix form a single pattern where both sides have to match
+        let mut parts = Vec::new();
+        if let Some(prefix) = &self.prefix {
+            parts.push(PatternSpec::new(prefix, Position::Start, self.prefix_case_insensitive));
+        }
+        if let Some(suffix) = &self.suffix {
+            parts.push(PatternSpec::new(suffix, Position::End, self.suffix_case_insensitive));
         }
-    }));
-    debug!("Created {} addresses", addresses.len());
-
-    stats.attempts.fetch_add(BATCH_SIZE as u64, Ordering::Relaxed);
-
-    // Check for matches
-    for (seed, address) in addresses {
-        if matches_pattern(
-            &address.to_string(),
-            pattern,
-            case_insensitive,
-            position,
-        ) {
-            debug!("Found matching address: {}", address);
-            stats.found.store(true, Ordering::Relaxed);
-            return Some((seed, address));
+        if !parts.is_empty() {
+            patterns.push(CompoundPattern::new(parts));
         }
+
+        Ok(patterns)
     }
+}
+
+/// How long the `estimate` subcommand grinds to measure the search speed.
+const MEASURE_DURATION: Duration = Duration::from_secs(3);
 
-    None
+fn format_duration(secs: f64) -> String {
+    if !secs.is_finite() {
+        return "forever".to_string();
+    }
+    let units = [("y", 31_536_000.0), ("d", 86_400.0), ("h", 3_600.0), ("m", 60.0), ("s", 1.0)];
+    for pair in units.windows(2) {
+        let ((unit, size), (next, next_size)) = (pair[0], pair[1]);
+        if secs >= size {
+            let whole = (secs / size).floor();
+            return format!("{}{} {}{}", whole, unit, ((secs - whole * size) / next_size).floor(), next);
+        }
+    }
+    format!("{:.1}s", secs)
 }
 
-#[cfg(feature = "gpu")]
-mod gpu {
-    use super::*;
-    use crate::metal::MetalDevice;
+fn estimate(patterns: &[CompoundPattern], rate: Option<f64>, mode: SearchMode, threads: Option<usize>) -> Result<()> {
+    let difficulty = estimate_difficulty(patterns)?;
+    let rate = match rate {
+        Some(rate) => rate,
+        None => {
+            info!("Measuring search speed for {}s", MEASURE_DURATION.as_secs());
+            measure_rate(patterns, mode, threads, MEASURE_DURATION)?
+        }
+    };
 
-    pub fn init_gpu() -> Result<MetalDevice> {
-        MetalDevice::new()
+    for compound in patterns {
+        println!("Pattern: {}", compound);
+    }
+    println!("Probability per attempt: {:.3e}", difficulty.probability);
+    println!("Expected attempts: {:.0}", difficulty.expected_attempts());
+    println!("Speed: {:.2}M attempts/s", rate / 1_000_000.0);
+    println!("Expected time: {}", format_duration(difficulty.expected_attempts() / rate));
+    for chance in [0.5, 0.9, 0.99] {
+        println!(
+            "{:.0}% chance within: {}",
+            chance * 100.0,
+            format_duration(difficulty.attempts_for_probability(chance) / rate)
+        );
     }
 
-    pub fn search_gpu_batch(
-        device: &MetalDevice,
-        base_keypair: &Keypair,
-        pattern: &str,
-        position: &str,
-        case_insensitive: bool,
-        stats: &SearchStats,
-    ) -> Option<(String, Pubkey)> {
-        device.search_batch(base_keypair, pattern, position, case_insensitive, stats)
+    Ok(())
+}
+
+fn bench(seconds: u64, threads: Option<usize>) -> Result<()> {
+    info!("Benchmarking each search loop for {}s", seconds);
+    let report = run_benchmark(threads, Duration::from_secs(seconds))?;
+
+    println!("Threads: {}", report.threads);
+    println!("Legacy loop: {:.2}M attempts/s", report.legacy_rate / 1_000_000.0);
+    println!("Current loop: {:.2}M attempts/s", report.rate / 1_000_000.0);
+    println!("Speedup: {:.2}x", report.speedup());
+    println!("Keypair search: {:.2}K keys/s", report.keypair_rate / 1_000.0);
+    println!("Expanded keypair search: {:.2}K keys/s", report.expanded_keypair_rate / 1_000.0);
+
+    Ok(())
+}
+
+fn reproduce(
+    master_seed: &[u8; 32],
+    mode: SearchMode,
+    seed_format: &SeedFormat,
+    owner: &OwnerProgram,
+    pda: Option<&PdaSeeds>,
+    mint: Option<&Pubkey>,
+    attempt_index: u64,
+) -> Result<()> {
+    match mode {
+        SearchMode::Seed => {}
+        SearchMode::Keypair => {
+            println!("Token address: {}", reproduce_keypair(master_seed, attempt_index)?.pubkey());
+            return Ok(());
+        }
+        SearchMode::ExpandedKeypair => {
+            println!("Token address: {}", reproduce_expanded_keypair(master_seed, attempt_index)?.pubkey());
+            return Ok(());
+        }
+        SearchMode::Pda => {
+            let pda = reproduce_pda(master_seed, seed_format, pda.expect("PDA mode has seeds"), attempt_index)?;
+            println!("Seeds: {}", pda.seeds.iter().map(hex::encode).collect::<Vec<String>>().join(" "));
+            println!("Bump: {}", pda.bump);
+            println!("Token address: {}", pda.address()?);
+            return Ok(());
+        }
+        SearchMode::Ata => {
+            let mint = mint.ok_or_else(|| anyhow!("An ATA search needs --mint"))?;
+            let wallet = reproduce_ata_wallet(master_seed, attempt_index)?.pubkey();
+            println!("Wallet: {}", wallet);
+            println!(
+                "Token address: {}",
+                get_associated_token_address_with_program_id(&wallet, mint, &owner.program_id())
+            );
+            return Ok(());
+        }
     }
+    let candidate = reproduce_candidate(master_seed, seed_format, owner, attempt_index)?;
+
+    println!("Base pubkey: {}", candidate.base_keypair.pubkey());
+    println!("Seed: {}", candidate.seed);
+    println!("Token address: {}", candidate.token_address);
+
+    Ok(())
 }
 
-fn main() -> Result<()> {
-    env_logger::init();
-    let args = Args::parse();
+/// Environment variable holding the checkpoint passphrase, prompted for otherwise.
+const PASSPHRASE_ENV: &str = "TOKEN22_VANITY_PASSPHRASE";
 
-    if args.position != "start" && args.position != "end" {
-        return Err(anyhow!("Position must be either 'start' or 'end'"));
+/// Checkpoints of pubkey-only searches hold no secret and need no passphrase.
+fn checkpoint_options(path: PathBuf, interval_secs: u64, needs_passphrase: bool) -> Result<CheckpointOptions> {
+    let passphrase = match std::env::var(PASSPHRASE_ENV) {
+        _ if !needs_passphrase => String::new(),
+        Ok(passphrase) => passphrase,
+        Err(_) => rpassword::prompt_password("Checkpoint passphrase: ")?,
+    };
+    if needs_passphrase && passphrase.is_empty() {
+        return Err(anyhow!("The checkpoint passphrase must not be empty"));
     }
 
-    info!("Starting vanity address search");
-    debug!("Arguments: {:?}", args);
-
-    let base_keypair = Keypair::new();
-    let stats = SearchStats::new();
-    let start_time = Instant::now();
-    let num_threads = args.threads.unwrap_or_else(num_cpus::get);
-
-    info!("Searching for pattern: {}", args.pattern);
-    info!("Position: {}", args.position);
-    info!("Case sensitive: {}", !args.case_insensitive);
-    info!("Using {} threads", num_threads);
-
-    // Initialize Metal device if GPU feature is enabled
-    #[cfg(feature = "gpu")]
-    let metal_device = if args.gpu {
-        match gpu::init_gpu() {
-            Ok(device) => {
-                info!("Metal GPU acceleration enabled");
-                Some(device)
-            }
-            Err(e) => {
-                warn!("Failed to initialize Metal GPU: {}", e);
-                warn!("Falling back to CPU");
-                None
-            }
+    Ok(CheckpointOptions {
+        path,
+        passphrase,
+        interval: Duration::from_secs(interval_secs),
+    })
+}
+
+/// Cancels the search on Ctrl-C or SIGTERM, so it can save a checkpoint and report
+/// its closest candidate before exiting.
+fn cancel_on_signal() -> Result<CancelHandle> {
+    let cancel = CancelHandle::new();
+    let handle = cancel.clone();
+    ctrlc::set_handler(move || {
+        info!("Stopping search");
+        handle.cancel();
+    })?;
+    Ok(cancel)
+}
+
+/// Prints each match as soon as the search finds it.
+fn print_on_match(patterns: Vec<CompoundPattern>) -> MatchCallback {
+    MatchCallback::new(move |result: &VanityAddressResult| {
+        if let Err(e) = print_result(&patterns, result, None) {
+            error!("Failed to print match {}: {}", result.token_address, e);
         }
+    })
+}
+
+fn log_progress(event: &ProgressEvent) {
+    // Only searches that can stop early keep track of the closest candidate
+    let best = event
+        .best_match
+        .as_ref()
+        .map_or(String::new(), |best| format!(", Best: {} characters", best.matched_chars));
+    info!(
+        "Speed: {:.2}M attempts/s, Total: {}M attempts, Chance of a match so far: {:.1}%, Expected time: {}{} ({})",
+        event.rate / 1_000_000.0,
+        event.attempts / 1_000_000,
+        event.chance * 100.0,
+        format_duration(event.eta),
+        best,
+        event.backend
+    );
+}
+
+/// Matches were already printed as they came in, so only the keypairs and, for an
+/// incomplete search, the summary are left.
+fn finish_collection(patterns: &[CompoundPattern], count: usize, collection: MatchCollection) -> Result<()> {
+    for result in &collection.matches {
+        save_keypair(Path::new(KEYS_DIR), result, count)?;
+    }
+
+    let Some(summary) = collection.incomplete else {
+        return Ok(());
+    };
+    print_summary(patterns, &summary);
+    if collection.matches.is_empty() {
+        Err(anyhow!("No match found: {}", summary.reason))
     } else {
-        None
+        Err(anyhow!(
+            "Found {} of {} matches: {}",
+            collection.matches.len(),
+            count,
+            summary.reason
+        ))
+    }
+}
+
+fn print_summary(patterns: &[CompoundPattern], summary: &SearchSummary) {
+    println!("Attempts: {}", summary.attempts);
+    println!("Time taken: {}", format_duration(summary.time_taken));
+    if let Some(best) = &summary.best_match {
+        println!(
+            "Closest candidate: {} ({} of {} characters of {})",
+            best.token_address, best.matched_chars, best.pattern_chars, patterns[best.matched_pattern]
+        );
+        println!("Seed: {}", best.seed);
+        println!("Owner: {}", best.owner);
+        println!("Attempt index: {}", best.attempt_index);
+    }
+}
+
+/// `lengths` holds the matched and requested characters of a best effort result.
+fn print_result(
+    patterns: &[CompoundPattern],
+    result: &VanityAddressResult,
+    lengths: Option<(usize, usize)>,
+) -> Result<()> {
+    // Verify the address matches what we found
+    let token_address = match result.mode {
+        SearchMode::Seed => {
+            let base_pubkey = Pubkey::from_str(&result.base_pubkey)?;
+            Pubkey::create_with_seed(&base_pubkey, &result.seed, &result.owner.program_id())?
+        }
+        SearchMode::Keypair => {
+            let keypair_json = result.keypair_json.as_ref().ok_or_else(|| anyhow!("Keypair result without a keypair"))?;
+            read_keypair(&mut keypair_json.as_bytes()).map_err(|e| anyhow!("Invalid keypair: {}", e))?.pubkey()
+        }
+        SearchMode::ExpandedKeypair => {
+            let keypair_json = result.keypair_json.as_ref().ok_or_else(|| anyhow!("Keypair result without a keypair"))?;
+            ExpandedKeypair::from_bytes(&serde_json::from_str::<Vec<u8>>(keypair_json)?)?.pubkey()
+        }
+        SearchMode::Pda => result.pda.as_ref().ok_or_else(|| anyhow!("PDA result without seeds"))?.address()?,
+        SearchMode::Ata => {
+            let keypair_json = result.keypair_json.as_ref().ok_or_else(|| anyhow!("ATA result without a wallet"))?;
+            let wallet = read_keypair(&mut keypair_json.as_bytes()).map_err(|e| anyhow!("Invalid keypair: {}", e))?;
+            let mint = Pubkey::from_str(result.mint.as_ref().ok_or_else(|| anyhow!("ATA result without a mint"))?)?;
+            get_associated_token_address_with_program_id(&wallet.pubkey(), &mint, &result.owner.program_id())
+        }
     };
+    assert_eq!(token_address.to_string(), result.token_address, "Token address mismatch!");
 
-    #[cfg(not(feature = "gpu"))]
-    let metal_device: Option<MetalDevice> = None;
+    // Print machine-readable output first
+    println!("RESULT_START");
+    println!("{}", result_json(patterns, result, lengths)?);
+    println!("RESULT_END");
 
-    if metal_device.is_none() {
-        info!("Using {} CPU threads", num_threads);
-        rayon::ThreadPoolBuilder::new()
-            .num_threads(num_threads)
-            .build_global()?;
-    }
+    Ok(())
+}
+
+/// The `RESULT_START`/`RESULT_END` block TypeScript parses. Optional fields are left
+/// out rather than null.
+#[derive(Serialize)]
+struct ResultJson<'a> {
+    mode: String,
+    base_pubkey: &'a str,
+    seed: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seeds_hex: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bump: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mint: Option<&'a str>,
+    token_address: &'a str,
+    owner: String,
+    matched_pattern: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attempt_index: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    matched_chars: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pattern_chars: Option<usize>,
+    time_taken: f64,
+    attempts: u64,
+}
 
-    let stats_clone = stats.clone();
-    let attempts_clone = stats.attempts.clone();
+fn result_json(
+    patterns: &[CompoundPattern],
+    result: &VanityAddressResult,
+    lengths: Option<(usize, usize)>,
+) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&ResultJson {
+        mode: result.mode.to_string(),
+        base_pubkey: &result.base_pubkey,
+        seed: &result.seed,
+        seeds_hex: result.pda.as_ref().map(|pda| pda.seeds.iter().map(hex::encode).collect()),
+        bump: result.pda.as_ref().map(|pda| pda.bump),
+        mint: result.mint.as_deref(),
+        token_address: &result.token_address,
+        owner: result.owner.program_id().to_string(),
+        matched_pattern: patterns[result.matched_pattern].to_string(),
+        attempt_index: result.attempt_index,
+        matched_chars: lengths.map(|(matched_chars, _)| matched_chars),
+        pattern_chars: lengths.map(|(_, pattern_chars)| pattern_chars),
+        time_taken: result.time_taken,
+        attempts: result.attempts,
+    })?)
+}
 
-    // Progress reporting thread
-    std::thread::spawn(move || {
-        let mut last_attempts = 0u64;
-        let mut last_time = Instant::now();
+/// Directory the keypairs of matches are saved to.
+const KEYS_DIR: &str = "token_keys";
 
-        while !stats_clone.found.load(Ordering::Relaxed) {
-            std::thread::sleep(std::time::Duration::from_secs(1));
-            let current_attempts = attempts_clone.load(Ordering::Relaxed);
-            let attempts_delta = current_attempts - last_attempts;
-            let time_delta = last_time.elapsed().as_secs_f64();
-            
-            info!(
-                "Speed: {:.2}M attempts/s, Total: {}M attempts",
-                attempts_delta as f64 / time_delta / 1_000_000.0,
-                current_attempts / 1_000_000
+/// Saves the keypair of a match in `dir`. The matches of a search for more than
+/// one get their address appended to the file name, so none overwrites another.
+fn save_keypair(dir: &Path, result: &VanityAddressResult, count: usize) -> Result<()> {
+    let Some(keypair_json) = &result.keypair_json else {
+        match result.mode {
+            SearchMode::Pda => info!("PDA search, no keypair to save"),
+            _ => info!("Pubkey-only search, no keypair to save"),
+        }
+        return Ok(());
+    };
+
+    // A single match is saved only as token_keypair.json for TypeScript to rename.
+    // Expanded keypairs get their own name, nothing but a Rust signer can load them,
+    // and so do ATA wallets, which aren't mint keys.
+    let name = match result.mode {
+        SearchMode::ExpandedKeypair => "token_expanded_keypair",
+        SearchMode::Ata => "ata_wallet_keypair",
+        SearchMode::Seed | SearchMode::Keypair | SearchMode::Pda => "token_keypair",
+    };
+    let path = match count {
+        1 => dir.join(format!("{}.json", name)),
+        _ => dir.join(format!("{}_{}.json", name, result.token_address)),
+    };
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(&path, keypair_json)?;
+
+    info!("Keypair saved to: {}", path.display());
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    env_logger::init();
+    let args = Args::parse();
+
+    match &args.command {
+        Some(Command::Estimate { patterns, rate, mode, threads }) => {
+            return estimate(&patterns.to_patterns()?, *rate, *mode, *threads);
+        }
+        Some(Command::Bench { seconds, threads }) => return bench(*seconds, *threads),
+        Some(Command::Reproduce { master_seed, attempt_index, mode, seed_format, owner, pda, mint }) => {
+            let pda = pda.to_seeds(*mode)?;
+            return reproduce(
+                master_seed,
+                *mode,
+                &seed_format.to_format()?,
+                owner,
+                pda.as_ref(),
+                mint.as_ref(),
+                *attempt_index,
             );
+        }
+        Some(Command::Resume { checkpoint, threads, checkpoint_interval, budget }) => {
+            let checkpoint_path = checkpoint.clone();
+            let checkpoint = Checkpoint::load(checkpoint)?;
+            let needs_passphrase = checkpoint.encrypted_base_keypair.is_some();
+            let options = SearchOptions {
+                threads: *threads,
+                checkpoint: Some(checkpoint_options(checkpoint_path, *checkpoint_interval, needs_passphrase)?),
+                cancel: Some(cancel_on_signal()?),
+                timeout: budget.timeout(),
+                max_attempts: budget.max_attempts,
+                on_progress: Some(ProgressCallback::new(log_progress)),
+                on_match: Some(print_on_match(checkpoint.patterns.clone())),
+                ..Default::default()
+            };
+            let (patterns, count) = (checkpoint.patterns.clone(), checkpoint.match_count);
+            for compound in &patterns {
+                info!("Searching for pattern: {}", compound);
+            }
 
-            last_attempts = current_attempts;
-            last_time = Instant::now();
+            let collection = resume_vanity_address(checkpoint, &options).await?;
+            return finish_collection(&patterns, count, collection);
         }
-    });
-
-    // Main search loop
-    loop {
-        let result = if let Some(device) = metal_device.as_ref() {
-            debug!("Using GPU for search batch");
-            device.search_batch(
-                &base_keypair,
-                &args.pattern,
-                &args.position,
-                args.case_insensitive,
-                &stats,
-            )
-        } else {
-            debug!("Using CPU for search batch");
-            search_batch(
-                &base_keypair,
-                &args.pattern,
-                &args.position,
-                args.case_insensitive,
-                &stats,
-            )
-        };
+        None => {}
+    }
 
-        if let Some((seed, address)) = result {
-            let elapsed = start_time.elapsed();
-            let attempts = stats.attempts.load(Ordering::Relaxed);
-            
-            // Create the token address using the seed
-            let token_address = Pubkey::create_with_seed(
-                &base_keypair.pubkey(),
-                &seed,
-                &TOKEN_PROGRAM_ID,
-            )?;
-
-            // Verify the address matches what we found
-            assert_eq!(token_address, address, "Token address mismatch!");
-            
-            // Print machine-readable output first
-            println!("RESULT_START");
-            println!("{{");
-            println!("  \"base_pubkey\": \"{}\",", base_keypair.pubkey());
-            println!("  \"seed\": \"{}\",", seed);
-            println!("  \"token_address\": \"{}\",", token_address);
-            println!("  \"time_taken\": {},", elapsed.as_secs_f64());
-            println!("  \"attempts\": {}", attempts);
-            println!("}}");
-            println!("RESULT_END");
-            
-            // Then print human-readable output
-            info!("Found matching address!");
-            info!("Base pubkey: {}", base_keypair.pubkey());
-            info!("Seed: {}", seed);
-            info!("Token address: {}", token_address);
-            info!("Time taken: {:.2}s", elapsed.as_secs_f64());
-            info!(
-                "Average speed: {:.2}M attempts/s",
-                attempts as f64 / elapsed.as_secs_f64() / 1_000_000.0
-            );
-            
-            // Save the keypair in Solana CLI format
-            std::fs::create_dir_all("token_keys")?;
-            let keypair_bytes = base_keypair.to_bytes();
-            let keypair_str = format!("[{}]", keypair_bytes.iter().map(|b| b.to_string()).collect::<Vec<String>>().join(","));
-            
-            // Save only as token_keypair.json for TypeScript to rename
-            std::fs::write("token_keys/token_keypair.json", &keypair_str)?;
+    let patterns = args.patterns.to_patterns()?;
 
-            info!("Keypair saved to: token_keys/token_keypair.json");
+    info!("Starting vanity address search");
+    // Not the whole of `args`, which may hold the master seed
+    debug!("Pattern arguments: {:?}", args.patterns);
 
-            return Ok(());
+    for compound in &patterns {
+        info!("Searching for pattern: {}", compound);
+    }
+
+    let base_key = match (&args.base_keypair, args.base_pubkey) {
+        (Some(path), _) => BaseKey::Keypair(Arc::new(read_keypair_file(path).map_err(|e| {
+            anyhow!("Failed to read base keypair {}: {}", path.display(), e)
+        })?)),
+        (None, Some(pubkey)) => BaseKey::Pubkey(pubkey),
+        (None, None) => BaseKey::Generate,
+    };
+    let checkpoint = args
+        .checkpoint
+        .map(|path| {
+            // Neither pubkey-only nor PDA searches hold a secret
+            let needs_passphrase = args.base_pubkey.is_none() && args.mode != SearchMode::Pda;
+            checkpoint_options(path, args.checkpoint_interval, needs_passphrase)
+        })
+        .transpose()?;
+    let options = SearchOptions {
+        mode: args.mode,
+        use_gpu: args.gpu,
+        threads: args.threads,
+        master_seed: args.master_seed,
+        base_key,
+        seed_format: args.seed_format.to_format()?,
+        pda: args.pda.to_seeds(args.mode)?,
+        ata_mint: args.mint,
+        owners: args.owner,
+        checkpoint,
+        cancel: Some(cancel_on_signal()?),
+        timeout: args.budget.timeout(),
+        max_attempts: args.budget.max_attempts,
+        track_best: args.best_effort,
+        on_progress: Some(ProgressCallback::new(log_progress)),
+        progress_interval: None,
+        on_match: None,
+    };
+
+    if args.best_effort {
+        let longest = find_longest_match(&patterns, &options).await?;
+        if let Some(reason) = longest.stopped {
+            info!(
+                "Best effort: {} of {} characters matched ({})",
+                longest.matched_chars, longest.pattern_chars, reason
+            );
         }
+        print_result(
+            &patterns,
+            &longest.result,
+            Some((longest.matched_chars, longest.pattern_chars)),
+        )?;
+        return save_keypair(Path::new(KEYS_DIR), &longest.result, 1);
     }
 
-    Ok(())
+    let options = SearchOptions {
+        on_match: Some(print_on_match(patterns.clone())),
+        ..options
+    };
+    let collection = collect_vanity_addresses(&patterns, args.count, &options).await?;
+
+    finish_collection(&patterns, args.count, collection)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use token22_vanity::matches_pattern;
+
+    #[tokio::test]
+    async fn test_every_match_keypair_is_saved() {
+        let patterns = vec![PatternSpec::new("a", Position::End, false).into()];
+        let options = SearchOptions {
+            mode: SearchMode::Keypair,
+            threads: Some(1),
+            ..Default::default()
+        };
+        let collection = collect_vanity_addresses(&patterns, 3, &options).await.unwrap();
+        assert_eq!(collection.matches.len(), 3);
+
+        let dir = std::env::temp_dir().join(format!("token22-vanity-keys-{}", rand::random::<u64>()));
+        for result in &collection.matches {
+            save_keypair(&dir, result, 3).unwrap();
+        }
+        let mut saved: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| read_keypair_file(entry.unwrap().path()).unwrap().pubkey().to_string())
+            .collect();
+        saved.sort();
+        let mut found: Vec<String> = collection.matches.iter().map(|result| result.token_address.clone()).collect();
+        found.sort();
+        assert_eq!(saved, found);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_result_json_escapes_the_seed() {
+        let patterns = vec![PatternSpec::new("a", Position::End, false).into()];
+        let options = SearchOptions {
+            threads: Some(1),
+            seed_format: SeedFormat {
+                prefix: "say\"hi\"\\".to_string(),
+                ..SeedFormat::default()
+            },
+            ..Default::default()
+        };
+        let result = collect_vanity_addresses(&patterns, 1, &options).await.unwrap().matches.remove(0);
+        assert!(result.seed.starts_with("say\"hi\"\\"));
+
+        let json: serde_json::Value = serde_json::from_str(&result_json(&patterns, &result, None).unwrap()).unwrap();
+        assert_eq!(json["seed"], result.seed);
+        assert_eq!(json["token_address"], result.token_address);
+        assert!(json.get("mint").is_none());
+    }
 
     #[test]
     fn test_pattern_matching() {
@@ -320,4 +834,4 @@ mod tests {
         assert!(matches_pattern("Hello", "he", true, "start"));
         assert!(!matches_pattern("hello", "HE", false, "start"));
     }
-} 
\ No newline at end of file
+}
@@ -3,7 +3,6 @@ use log::info;
 use metal::*;
 use objc::rc::autoreleasepool;
 use solana_program::pubkey::Pubkey;
-use solana_sdk::signature::{Keypair, Signer};
 
 use crate::SearchStats;
 
@@ -239,7 +238,7 @@ impl MetalDevice {
 
     pub fn search_batch(
         &self,
-        base_keypair: &Keypair,
+        base_pubkey: &Pubkey,
         pattern: &str,
         position: &str,
         case_insensitive: bool,
@@ -247,12 +246,12 @@ impl MetalDevice {
     ) -> Option<(String, Pubkey)> {
         autoreleasepool(|| {
             // Create buffers
-            let base_pubkey = base_keypair.pubkey().to_bytes();
+            let base_bytes = base_pubkey.to_bytes();
             let pattern_bytes = pattern.as_bytes();
             
             let base_buffer = self.device.new_buffer_with_data(
-                base_pubkey.as_ptr() as *const _,
-                base_pubkey.len() as u64,
+                base_bytes.as_ptr() as *const _,
+                base_bytes.len() as u64,
                 MTLResourceOptions::StorageModeShared,
             );
             
@@ -313,9 +312,9 @@ impl MetalDevice {
                 
                 let seed = hex::encode(seed_bytes);
                 let address = Pubkey::create_with_seed(
-                    &base_keypair.pubkey(),
+                    base_pubkey,
                     &seed,
-                    &crate::TOKEN_PROGRAM_ID,
+                    &crate::owner::TOKEN_2022_PROGRAM_ID,
                 ).unwrap();
                 
                 Some((seed, address))
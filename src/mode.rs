@@ -0,0 +1,51 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// What kind of address a search grinds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SearchMode {
+    /// `create_with_seed` addresses of the base key, for `create_account_with_seed`.
+    #[default]
+    Seed,
+    /// Public keys of mint keypairs, for mints created with `create_account`.
+    Keypair,
+    /// Like `Keypair`, but successive keys are one point addition apart. Much
+    /// faster, though the keys are `ExpandedKeypair`s only Rust signers can use.
+    ExpandedKeypair,
+    /// Program derived addresses, grinding one seed among fixed ones so the PDA at
+    /// the canonical bump matches.
+    Pda,
+    /// Wallet keypairs whose associated token account for a given mint matches.
+    Ata,
+}
+
+impl FromStr for SearchMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "seed" => Ok(SearchMode::Seed),
+            "keypair" => Ok(SearchMode::Keypair),
+            "expanded-keypair" => Ok(SearchMode::ExpandedKeypair),
+            "pda" => Ok(SearchMode::Pda),
+            "ata" => Ok(SearchMode::Ata),
+            _ => Err(anyhow!(
+                "Search mode must be 'seed', 'keypair', 'expanded-keypair', 'pda' or 'ata', got '{}'",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SearchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchMode::Seed => write!(f, "seed"),
+            SearchMode::Keypair => write!(f, "keypair"),
+            SearchMode::ExpandedKeypair => write!(f, "expanded-keypair"),
+            SearchMode::Pda => write!(f, "pda"),
+            SearchMode::Ata => write!(f, "ata"),
+        }
+    }
+}
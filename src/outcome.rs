@@ -0,0 +1,105 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::{OwnerProgram, VanityAddressResult};
+
+/// How a search with a cancel handle or budget ended.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum SearchOutcome {
+    Found(VanityAddressResult),
+    NotFound(SearchSummary),
+}
+
+impl SearchOutcome {
+    /// The match, or an error saying why the search stopped without one.
+    pub fn into_found(self) -> Result<VanityAddressResult> {
+        match self {
+            SearchOutcome::Found(result) => Ok(result),
+            SearchOutcome::NotFound(summary) => Err(anyhow!(
+                "No match found after {} attempts: {}",
+                summary.attempts,
+                summary.reason
+            )),
+        }
+    }
+}
+
+/// The matches of a search collecting several addresses.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MatchCollection {
+    /// Matches in the order they were found.
+    pub matches: Vec<VanityAddressResult>,
+    /// Why the search stopped before collecting every requested match.
+    pub incomplete: Option<SearchSummary>,
+}
+
+impl MatchCollection {
+    /// The first match, or why the search stopped without one.
+    pub(crate) fn into_outcome(self) -> SearchOutcome {
+        match (self.matches.into_iter().next(), self.incomplete) {
+            (Some(result), _) => SearchOutcome::Found(result),
+            (None, Some(summary)) => SearchOutcome::NotFound(summary),
+            (None, None) => unreachable!("a complete collection holds at least one match"),
+        }
+    }
+}
+
+/// The candidate a best effort search ended on.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LongestMatch {
+    pub result: VanityAddressResult,
+    /// Characters of the start and end parts of the matched pattern the candidate has.
+    pub matched_chars: usize,
+    /// Characters in the start and end parts of the matched pattern.
+    pub pattern_chars: usize,
+    /// Why the search stopped short of a full match, `None` when it found one.
+    pub stopped: Option<StopReason>,
+}
+
+/// What a search that stopped early got through.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchSummary {
+    pub reason: StopReason,
+    pub attempts: u64,
+    pub time_taken: f64,
+    /// The candidate that came closest to a pattern, if any matched a character.
+    /// Searches without a cancel handle or budget only keep it with
+    /// `SearchOptions::track_best`.
+    pub best_match: Option<PartialMatch>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StopReason {
+    Cancelled,
+    Timeout,
+    MaxAttempts,
+    /// Every seed the seed format can write was tried.
+    SeedsExhausted,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Cancelled => write!(f, "search cancelled"),
+            StopReason::Timeout => write!(f, "timeout reached"),
+            StopReason::MaxAttempts => write!(f, "attempt budget exhausted"),
+            StopReason::SeedsExhausted => write!(f, "every seed of the seed format tried"),
+        }
+    }
+}
+
+/// A candidate that matched part of a pattern: the characters of its start and
+/// end parts matched in a row from the anchored side.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartialMatch {
+    pub seed: String,
+    pub token_address: String,
+    pub owner: OwnerProgram,
+    pub attempt_index: u64,
+    /// Index of the pattern the candidate came closest to.
+    pub matched_pattern: usize,
+    pub matched_chars: usize,
+    /// Characters in the start and end parts of that pattern.
+    pub pattern_chars: usize,
+}
@@ -0,0 +1,112 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use solana_program::pubkey::Pubkey;
+use std::{fmt, str::FromStr};
+
+pub(crate) const SPL_TOKEN_PROGRAM_ID: Pubkey =
+    solana_program::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub(crate) const TOKEN_2022_PROGRAM_ID: Pubkey =
+    solana_program::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// The program owning the ground addresses, the `owner` of `create_with_seed`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum OwnerProgram {
+    SplToken,
+    #[default]
+    Token2022,
+    Custom(Pubkey),
+}
+
+impl OwnerProgram {
+    pub fn program_id(&self) -> Pubkey {
+        match self {
+            OwnerProgram::SplToken => SPL_TOKEN_PROGRAM_ID,
+            OwnerProgram::Token2022 => TOKEN_2022_PROGRAM_ID,
+            OwnerProgram::Custom(program_id) => *program_id,
+        }
+    }
+
+    /// Fails for owners `create_with_seed` rejects with `IllegalOwner`, those ending
+    /// in the program derived address marker. The search hashes addresses itself, so
+    /// it would otherwise report addresses no transaction can create.
+    pub fn validate(&self) -> Result<()> {
+        Pubkey::create_with_seed(&Pubkey::default(), "", &self.program_id())
+            .map(|_| ())
+            .map_err(|e| anyhow!("Owner {} can't own addresses created with a seed: {}", self, e))
+    }
+}
+
+impl FromStr for OwnerProgram {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "spl-token" => Ok(OwnerProgram::SplToken),
+            "token-2022" => Ok(OwnerProgram::Token2022),
+            _ => {
+                let program_id = Pubkey::from_str(s).map_err(|_| {
+                    anyhow!("Owner must be 'spl-token', 'token-2022' or a program id, got '{}'", s)
+                })?;
+                // Presets print by name, so a preset's id parses to the preset
+                Ok(match program_id {
+                    SPL_TOKEN_PROGRAM_ID => OwnerProgram::SplToken,
+                    TOKEN_2022_PROGRAM_ID => OwnerProgram::Token2022,
+                    _ => OwnerProgram::Custom(program_id),
+                })
+            }
+        }
+    }
+}
+
+impl fmt::Display for OwnerProgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OwnerProgram::SplToken => write!(f, "spl-token"),
+            OwnerProgram::Token2022 => write!(f, "token-2022"),
+            OwnerProgram::Custom(program_id) => write!(f, "{}", program_id),
+        }
+    }
+}
+
+impl TryFrom<String> for OwnerProgram {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<OwnerProgram> for String {
+    fn from(owner: OwnerProgram) -> Self {
+        owner.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::pubkey::PUBKEY_BYTES;
+
+    #[test]
+    fn test_parse_and_validate_owners() {
+        for (s, owner) in [
+            ("spl-token", OwnerProgram::SplToken),
+            ("token-2022", OwnerProgram::Token2022),
+            ("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA", OwnerProgram::SplToken),
+        ] {
+            assert_eq!(s.parse::<OwnerProgram>().unwrap(), owner);
+            assert!(owner.validate().is_ok());
+        }
+
+        let custom = Pubkey::new_unique();
+        let owner: OwnerProgram = custom.to_string().parse().unwrap();
+        assert_eq!(owner, OwnerProgram::Custom(custom));
+        assert_eq!(owner.to_string().parse::<OwnerProgram>().unwrap(), owner);
+
+        let mut illegal = [7u8; PUBKEY_BYTES];
+        illegal[PUBKEY_BYTES - 21..].copy_from_slice(b"ProgramDerivedAddress");
+        assert!(OwnerProgram::Custom(Pubkey::new_from_array(illegal)).validate().is_err());
+        assert!("not-an-owner".parse::<OwnerProgram>().is_err());
+    }
+}
@@ -0,0 +1,181 @@
+use crate::pattern::{CharClass, CompiledPattern, Position, BASE58_ALPHABET};
+use crate::prefix::PrefixRanges;
+use crate::suffix::{ResidueReducer, MAX_SUFFIX_LEN};
+
+/// Scores how close a raw address comes to each pattern, without encoding it: the
+/// length of the longest leading piece of each start and end part the address has
+/// in place, so "gemstone" at the end scores 4 for an address ending in "gems".
+/// Other positions only count once the whole pattern matches.
+#[derive(Debug, Clone)]
+pub(crate) struct PartialScorer {
+    /// Present when any pattern has an end part.
+    reducer: Option<ResidueReducer>,
+    compounds: Vec<Vec<PartRun>>,
+    /// Characters in the start and end parts of each compound pattern.
+    pattern_chars: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+enum PartRun {
+    /// `levels[j]` holds the addresses matching the first `j + 1` characters.
+    Start(Vec<PrefixRanges>),
+    /// Classes of the first `MAX_SUFFIX_LEN` characters.
+    End(Vec<CharClass>),
+}
+
+impl PartialScorer {
+    pub(crate) fn new(compiled: &[Vec<CompiledPattern>]) -> Self {
+        let compounds: Vec<Vec<PartRun>> = compiled
+            .iter()
+            .map(|parts| {
+                parts
+                    .iter()
+                    .filter_map(|part| match part.position {
+                        Position::Start | Position::At(0) => {
+                            Some(PartRun::Start(PrefixRanges::levels(&part.classes)))
+                        }
+                        Position::End => Some(PartRun::End(
+                            part.classes.iter().take(MAX_SUFFIX_LEN).copied().collect(),
+                        )),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let pattern_chars = compiled
+            .iter()
+            .map(|parts| {
+                parts
+                    .iter()
+                    .filter(|part| matches!(part.position, Position::Start | Position::At(0) | Position::End))
+                    .map(|part| part.classes.len())
+                    .sum()
+            })
+            .collect();
+
+        let reducer = compounds
+            .iter()
+            .flatten()
+            .filter_map(|run| match run {
+                PartRun::End(classes) => Some(classes.len()),
+                PartRun::Start(_) => None,
+            })
+            .max()
+            .map(|len| ResidueReducer::new((BASE58_ALPHABET.len() as u64).pow(len as u32)));
+
+        Self {
+            reducer,
+            compounds,
+            pattern_chars,
+        }
+    }
+
+    pub(crate) fn pattern_chars(&self, compound: usize) -> usize {
+        self.pattern_chars[compound]
+    }
+
+    /// The compound pattern the address comes closest to and how many characters
+    /// of it match.
+    pub(crate) fn score(&self, address: &[u8; 32]) -> (usize, usize) {
+        let mut residue = None;
+        let mut best = (0, 0);
+        for (i, runs) in self.compounds.iter().enumerate() {
+            let score = runs
+                .iter()
+                .map(|run| match run {
+                    PartRun::Start(levels) => levels.iter().take_while(|level| level.contains(address)).count(),
+                    PartRun::End(classes) => {
+                        let residue = *residue.get_or_insert_with(|| {
+                            self.reducer
+                                .as_ref()
+                                .expect("reducer exists for end parts")
+                                .residue(address)
+                        });
+                        end_run(residue, classes)
+                    }
+                })
+                .sum();
+            if score > best.1 {
+                best = (i, score);
+            }
+        }
+        best
+    }
+}
+
+/// Longest `k` such that the address, whose last digits are those of `residue`,
+/// ends with the first `k` classes.
+fn end_run(residue: u64, classes: &[CharClass]) -> usize {
+    let mut tail = [0u8; MAX_SUFFIX_LEN];
+    let mut rest = residue;
+    for digit in tail.iter_mut().take(classes.len()) {
+        *digit = BASE58_ALPHABET[(rest % BASE58_ALPHABET.len() as u64) as usize];
+        rest /= BASE58_ALPHABET.len() as u64;
+    }
+
+    // tail[0] is the last character, so a run of k ends at tail[k - 1]
+    (1..=classes.len())
+        .rev()
+        .find(|&k| {
+            classes[..k]
+                .iter()
+                .zip(tail[..k].iter().rev())
+                .all(|(class, &c)| class.contains(c))
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{base58, PatternSet, PatternSpec};
+
+    #[test]
+    fn test_scores_match_encoded_runs() {
+        let set = PatternSet::new(&[
+            PatternSpec::new("Gxyz", Position::Start, true).into(),
+            PatternSpec::new("gems", Position::End, true).into(),
+        ])
+        .unwrap();
+        let scorer = PartialScorer::new(set.compiled());
+        let mut out = [0u8; base58::MAX_ENCODED_LEN];
+
+        let run = |encoded: &[u8], pattern: &[u8], from_end: bool| -> usize {
+            (0..=pattern.len())
+                .rev()
+                .find(|&k| {
+                    let piece = if from_end { &encoded[encoded.len() - k..] } else { &encoded[..k] };
+                    piece.eq_ignore_ascii_case(&pattern[..k])
+                })
+                .unwrap()
+        };
+
+        for _ in 0..20_000 {
+            let address = rand::random::<[u8; 32]>();
+            let encoded = base58::encode_32(&address, &mut out);
+            let start = run(encoded, b"Gxyz", false);
+            let end = run(encoded, b"gems", true);
+            let expected = if end > start { (1, end) } else if start > 0 { (0, start) } else { (0, 0) };
+            assert_eq!(scorer.score(&address), expected);
+        }
+    }
+
+    #[test]
+    fn test_end_run_takes_leading_piece_of_target() {
+        let classes = CompiledPattern::compile(&PatternSpec::new("gemstone", Position::End, false))
+            .unwrap()
+            .classes;
+        let residue = |tail: &str| {
+            tail.bytes().fold(0u64, |acc, c| {
+                acc * 58 + BASE58_ALPHABET.iter().position(|&a| a == c).unwrap() as u64
+            })
+        };
+
+        // Residues mod 58^8 of addresses with these last eight characters
+        assert_eq!(end_run(residue("xyzxgems"), &classes), 4);
+        assert_eq!(end_run(residue("xyzxxems"), &classes), 0);
+        assert_eq!(end_run(residue("gemstone"), &classes), 8);
+        assert_eq!(end_run(residue("zzzzzzzg"), &classes), 1);
+    }
+}
@@ -0,0 +1,621 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+use crate::base58::{self, MAX_ENCODED_LEN};
+use crate::estimate::Difficulty;
+use crate::filter::AddressFilter;
+use crate::partial::PartialScorer;
+
+pub(crate) const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Length of a base58 encoded 32-byte address. Shorter encodings exist but are rare.
+pub(crate) const ADDRESS_LEN: usize = 44;
+
+/// Where in the base58 address a pattern has to appear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Position {
+    Start,
+    End,
+    /// Anywhere in the address.
+    Contains,
+    /// At a fixed character offset from the start of the address.
+    At(usize),
+}
+
+impl FromStr for Position {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "start" => Ok(Position::Start),
+            "end" => Ok(Position::End),
+            "contains" => Ok(Position::Contains),
+            _ => match s.strip_prefix("at:") {
+                Some(offset) => offset
+                    .parse()
+                    .map(Position::At)
+                    .map_err(|_| anyhow!("Invalid offset in position '{}'", s)),
+                None => Err(anyhow!(
+                    "Position must be one of 'start', 'end', 'contains' or 'at:<offset>', got '{}'",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Position::Start => write!(f, "start"),
+            Position::End => write!(f, "end"),
+            Position::Contains => write!(f, "contains"),
+            Position::At(offset) => write!(f, "at:{}", offset),
+        }
+    }
+}
+
+/// Why a pattern was rejected before the search started.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternError {
+    /// No patterns were given at all.
+    NoPatterns,
+    /// A pattern (or compound pattern) has nothing to match.
+    Empty,
+    /// The wildcard syntax could not be parsed.
+    Syntax { pattern: String, message: String },
+    /// The pattern uses characters that never occur in a base58 address. Members
+    /// of a character class count too, `[0a]` is rejected rather than read as `[a]`.
+    InvalidCharacters {
+        pattern: String,
+        invalid: Vec<InvalidChar>,
+    },
+    /// The pattern cannot fit into an address at its position.
+    TooLong { pattern: String, max: usize },
+}
+
+/// A character that can never match, together with base58 lookalikes to use instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidChar {
+    pub character: char,
+    /// The character class it is a member of, e.g. `[0a]`.
+    pub class: Option<String>,
+    pub suggestions: Vec<char>,
+}
+
+impl InvalidChar {
+    fn new(character: char, class: Option<String>, case_insensitive: bool) -> Self {
+        let lookalikes: &[char] = match character {
+            '0' => &['o'],
+            'O' => &['o'],
+            'I' => &['1', 'i'],
+            'l' => &['1', 'L', 'i'],
+            _ => &[],
+        };
+        // Under case folding a lookalike that only differs in case adds nothing
+        let suggestions = lookalikes
+            .iter()
+            .copied()
+            .filter(|s| !(case_insensitive && s.eq_ignore_ascii_case(&character)))
+            .collect();
+
+        Self {
+            character,
+            class,
+            suggestions,
+        }
+    }
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::NoPatterns => write!(f, "At least one pattern is required"),
+            PatternError::Empty => write!(f, "Pattern must not be empty"),
+            PatternError::Syntax { pattern, message } => {
+                write!(f, "Invalid pattern '{}': {}", pattern, message)
+            }
+            PatternError::InvalidCharacters { pattern, invalid } => {
+                write!(
+                    f,
+                    "Pattern '{}' can never match, base58 addresses do not contain ",
+                    pattern
+                )?;
+                for (i, c) in invalid.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{:?}", c.character)?;
+                    if let Some(class) = &c.class {
+                        write!(f, " in {}", class)?;
+                    }
+                    if !c.suggestions.is_empty() {
+                        let suggestions: Vec<String> =
+                            c.suggestions.iter().map(|s| format!("{:?}", s)).collect();
+                        write!(f, " (try {})", suggestions.join(" or "))?;
+                    }
+                }
+                Ok(())
+            }
+            PatternError::TooLong { pattern, max } => write!(
+                f,
+                "Pattern '{}' does not fit into a {}-character address at its position",
+                pattern, max
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+/// A single vanity pattern together with where it must appear and how it is compared.
+///
+/// Besides literal characters the pattern understands a small wildcard language:
+/// `?` matches any base58 character and `[...]` matches any of the listed characters,
+/// including ranges such as `[1-9]` (e.g. `[Gg]ems` or `Tx?[1-9][1-9]`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PatternSpec {
+    pub pattern: String,
+    pub position: Position,
+    pub case_insensitive: bool,
+}
+
+impl PatternSpec {
+    pub fn new(pattern: &str, position: Position, case_insensitive: bool) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            position,
+            case_insensitive,
+        }
+    }
+
+    /// Checks a single address. Malformed patterns never match; searches compile and
+    /// validate their patterns up front through `PatternSet` instead.
+    pub fn matches(&self, address: &str) -> bool {
+        CompiledPattern::compile_unchecked(self)
+            .is_ok_and(|compiled| compiled.matches(address.as_bytes()))
+    }
+
+    /// Whether the pattern uses no wildcards or character classes.
+    pub fn is_literal(&self) -> bool {
+        !self.pattern.contains(['?', '['])
+    }
+}
+
+impl fmt::Display for PatternSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}", self.pattern, self.position)?;
+        if self.case_insensitive {
+            write!(f, ", case insensitive")?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Several patterns that must all hold for the same address, e.g. a start pattern
+/// together with an end pattern.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompoundPattern {
+    pub parts: Vec<PatternSpec>,
+}
+
+impl CompoundPattern {
+    pub fn new(parts: Vec<PatternSpec>) -> Self {
+        Self { parts }
+    }
+
+    pub fn prefix_suffix(prefix: PatternSpec, suffix: PatternSpec) -> Self {
+        Self::new(vec![prefix, suffix])
+    }
+
+    pub fn matches(&self, address: &str) -> bool {
+        self.parts.iter().all(|part| part.matches(address))
+    }
+}
+
+impl From<PatternSpec> for CompoundPattern {
+    fn from(spec: PatternSpec) -> Self {
+        Self::new(vec![spec])
+    }
+}
+
+impl fmt::Display for CompoundPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, part) in self.parts.iter().enumerate() {
+            if i > 0 {
+                write!(f, " + ")?;
+            }
+            write!(f, "{}", part)?;
+        }
+        Ok(())
+    }
+}
+
+/// Set of ASCII characters accepted at one pattern position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CharClass(u128);
+
+impl CharClass {
+    fn any() -> Self {
+        BASE58_ALPHABET
+            .iter()
+            .fold(Self(0), |class, &b| class.with(b as char, false))
+    }
+
+    fn with(self, c: char, case_insensitive: bool) -> Self {
+        if !c.is_ascii() {
+            return self;
+        }
+        let mut bits = self.0 | 1u128 << c as u32;
+        if case_insensitive {
+            bits |= 1u128 << c.to_ascii_lowercase() as u32;
+            bits |= 1u128 << c.to_ascii_uppercase() as u32;
+        }
+        Self(bits)
+    }
+
+    pub(crate) fn contains(self, b: u8) -> bool {
+        b < 128 && self.0 & (1u128 << b) != 0
+    }
+
+    /// Number of base58 characters in the class.
+    pub(crate) fn base58_count(self) -> usize {
+        BASE58_ALPHABET.iter().filter(|&&b| self.contains(b)).count()
+    }
+}
+
+/// A `PatternSpec` parsed into one `CharClass` per position, with case folding
+/// already applied, so matching an address is a plain byte comparison.
+#[derive(Debug, Clone)]
+pub(crate) struct CompiledPattern {
+    pub(crate) classes: Vec<CharClass>,
+    pub(crate) position: Position,
+}
+
+impl CompiledPattern {
+    /// Compiles a pattern for searching, rejecting anything no base58 address can match.
+    pub(crate) fn compile(spec: &PatternSpec) -> Result<Self, PatternError> {
+        let (compiled, invalid) = Self::parse(spec)?;
+
+        if !invalid.is_empty() {
+            return Err(PatternError::InvalidCharacters {
+                pattern: spec.pattern.clone(),
+                invalid,
+            });
+        }
+        if compiled.classes.iter().any(|class| class.base58_count() == 0) {
+            return Err(PatternError::Syntax {
+                pattern: spec.pattern.clone(),
+                message: "character class matches no base58 character".to_string(),
+            });
+        }
+
+        let reach = match spec.position {
+            Position::At(offset) => offset.checked_add(compiled.classes.len()),
+            _ => Some(compiled.classes.len()),
+        };
+        if reach.is_none_or(|reach| reach > ADDRESS_LEN) {
+            return Err(PatternError::TooLong {
+                pattern: spec.pattern.clone(),
+                max: ADDRESS_LEN,
+            });
+        }
+
+        Ok(compiled)
+    }
+
+    /// Compiles a pattern without checking it against the base58 alphabet, for
+    /// matching arbitrary strings.
+    pub(crate) fn compile_unchecked(spec: &PatternSpec) -> Result<Self, PatternError> {
+        Self::parse(spec).map(|(compiled, _)| compiled)
+    }
+
+    /// Parses the wildcard syntax, also returning the characters that cannot occur
+    /// in a base58 address.
+    fn parse(spec: &PatternSpec) -> Result<(Self, Vec<InvalidChar>), PatternError> {
+        let ci = spec.case_insensitive;
+        let syntax = |message: String| PatternError::Syntax {
+            pattern: spec.pattern.clone(),
+            message,
+        };
+        let mut invalid = Vec::new();
+        let mut check = |c: char, class: Option<&[char]>| {
+            if CharClass(0).with(c, ci).base58_count() == 0
+                && !invalid.iter().any(|i: &InvalidChar| i.character == c)
+            {
+                let class = class.map(|members| format!("[{}]", members.iter().collect::<String>()));
+                invalid.push(InvalidChar::new(c, class, ci));
+            }
+        };
+
+        let mut classes = Vec::new();
+        let mut chars = spec.pattern.chars();
+
+        while let Some(c) = chars.next() {
+            let class = match c {
+                '?' => CharClass::any(),
+                '[' => {
+                    let mut class = CharClass(0);
+                    let mut members = Vec::new();
+                    loop {
+                        match chars.next() {
+                            Some(']') => break,
+                            Some(c) => members.push(c),
+                            None => return Err(syntax("unterminated '['".to_string())),
+                        }
+                    }
+                    if members.is_empty() {
+                        return Err(syntax("empty character class".to_string()));
+                    }
+
+                    let mut i = 0;
+                    while i < members.len() {
+                        if i + 2 < members.len() && members[i + 1] == '-' {
+                            let (from, to) = (members[i], members[i + 2]);
+                            if from > to {
+                                return Err(syntax(format!("invalid range '{}-{}'", from, to)));
+                            }
+                            // Ranges may span characters base58 leaves out, e.g. [a-z]
+                            for c in from..=to {
+                                class = class.with(c, ci);
+                            }
+                            i += 3;
+                        } else {
+                            check(members[i], Some(&members));
+                            class = class.with(members[i], ci);
+                            i += 1;
+                        }
+                    }
+                    class
+                }
+                ']' => return Err(syntax("unmatched ']'".to_string())),
+                c => {
+                    check(c, None);
+                    CharClass(0).with(c, ci)
+                }
+            };
+            classes.push(class);
+        }
+
+        if classes.is_empty() {
+            return Err(PatternError::Empty);
+        }
+
+        let compiled = Self {
+            classes,
+            position: spec.position,
+        };
+        Ok((compiled, invalid))
+    }
+
+    fn matches_at(&self, address: &[u8], offset: usize) -> bool {
+        offset.checked_add(self.classes.len()).is_some_and(|end| address.len() >= end)
+            && self.classes
+                .iter()
+                .zip(&address[offset..])
+                .all(|(class, &b)| class.contains(b))
+    }
+
+    pub(crate) fn matches(&self, address: &[u8]) -> bool {
+        let len = self.classes.len();
+        match self.position {
+            Position::Start => self.matches_at(address, 0),
+            Position::End => address.len() >= len && self.matches_at(address, address.len() - len),
+            Position::At(offset) => self.matches_at(address, offset),
+            Position::Contains => {
+                address.len() >= len && (0..=address.len() - len).any(|i| self.matches_at(address, i))
+            }
+        }
+    }
+}
+
+/// The list of patterns a search is looking for. An address matches the set if it
+/// matches any of its patterns. Patterns are compiled once when the set is built.
+#[derive(Debug, Clone)]
+pub struct PatternSet {
+    patterns: Vec<CompoundPattern>,
+    compiled: Vec<Vec<CompiledPattern>>,
+    address_filter: Option<AddressFilter>,
+    partial: PartialScorer,
+}
+
+impl PatternSet {
+    pub fn new(patterns: &[CompoundPattern]) -> Result<Self, PatternError> {
+        if patterns.is_empty() {
+            return Err(PatternError::NoPatterns);
+        }
+
+        let mut compiled = Vec::with_capacity(patterns.len());
+        for compound in patterns {
+            if compound.parts.is_empty() {
+                return Err(PatternError::Empty);
+            }
+            compiled.push(
+                compound
+                    .parts
+                    .iter()
+                    .map(CompiledPattern::compile)
+                    .collect::<Result<Vec<_>, _>>()?,
+            );
+        }
+
+        Ok(Self {
+            patterns: patterns.to_vec(),
+            address_filter: AddressFilter::new(&compiled),
+            partial: PartialScorer::new(&compiled),
+            compiled,
+        })
+    }
+
+    pub fn patterns(&self) -> &[CompoundPattern] {
+        &self.patterns
+    }
+
+    /// Returns the index of the first pattern the address satisfies.
+    pub fn find_match(&self, address: &str) -> Option<usize> {
+        self.find_match_bytes(address.as_bytes())
+    }
+
+    pub(crate) fn find_match_bytes(&self, address: &[u8]) -> Option<usize> {
+        self.compiled
+            .iter()
+            .position(|parts| parts.iter().all(|part| part.matches(address)))
+    }
+
+    /// Matches a raw address, base58 encoding it into `out` only when the address
+    /// filter cannot rule it out first.
+    pub(crate) fn find_match_address(
+        &self,
+        address: &[u8; 32],
+        out: &mut [u8; MAX_ENCODED_LEN],
+    ) -> Option<usize> {
+        if self.address_filter.as_ref().is_some_and(|filter| !filter.may_match(address)) {
+            return None;
+        }
+        self.find_match_bytes(base58::encode_32(address, out))
+    }
+
+    /// The pattern a raw address comes closest to and how many of its start and end
+    /// characters match.
+    pub(crate) fn partial_score(&self, address: &[u8; 32]) -> (usize, usize) {
+        self.partial.score(address)
+    }
+
+    pub(crate) fn pattern_chars(&self, compound: usize) -> usize {
+        self.partial.pattern_chars(compound)
+    }
+
+    pub(crate) fn compiled(&self) -> &[Vec<CompiledPattern>] {
+        &self.compiled
+    }
+
+    /// How likely a single candidate address is to match any of the patterns.
+    pub fn difficulty(&self) -> Difficulty {
+        Difficulty::of(self)
+    }
+
+    /// The pattern to hand to the GPU kernel, which only understands a single
+    /// literal start or end pattern.
+    pub(crate) fn gpu_pattern(&self) -> Option<&PatternSpec> {
+        match self.patterns.as_slice() {
+            [compound] => match compound.parts.as_slice() {
+                [spec]
+                    if spec.is_literal()
+                        && matches!(spec.position, Position::Start | Position::End) =>
+                {
+                    Some(spec)
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_match_reports_pattern_index() {
+        let set = PatternSet::new(&[
+            PatternSpec::new("gems", Position::End, false).into(),
+            PatternSpec::new("GEMS", Position::End, false).into(),
+            PatternSpec::new("Tx", Position::Start, true).into(),
+        ])
+        .unwrap();
+
+        assert_eq!(set.find_match("abcGEMS"), Some(1));
+        assert_eq!(set.find_match("txabcgems"), Some(0));
+        assert_eq!(set.find_match("TXabc"), Some(2));
+        assert_eq!(set.find_match("abc"), None);
+    }
+
+    #[test]
+    fn test_contains_and_offset_positions() {
+        let contains: Position = "contains".parse().unwrap();
+        let at: Position = "at:3".parse().unwrap();
+
+        assert!(PatternSpec::new("gem", contains, false).matches("abgemcd"));
+        assert!(PatternSpec::new("gem", at, false).matches("abcgemd"));
+        assert!(PatternSpec::new("GEM", at, true).matches("abcgemd"));
+        assert!(!PatternSpec::new("gem", at, false).matches("abgemcd"));
+        assert!(!PatternSpec::new("gem", "at:40".parse().unwrap(), false).matches("abgemcd"));
+        assert!(!PatternSpec::new("gem", Position::At(usize::MAX), false).matches("abgemcd"));
+        assert!(!crate::matches_pattern("abgemcd", "gem", false, &format!("at:{}", usize::MAX)));
+        assert_eq!(at.to_string(), "at:3");
+    }
+
+    #[test]
+    fn test_compound_pattern_requires_all_parts() {
+        let compound = CompoundPattern::prefix_suffix(
+            PatternSpec::new("Tx", Position::Start, false),
+            PatternSpec::new("GEMS", Position::End, true),
+        );
+
+        assert!(compound.matches("Txabcgems"));
+        assert!(!compound.matches("txabcgems"));
+        assert!(!compound.matches("Txabcgem"));
+    }
+
+    #[test]
+    fn test_wildcards_and_character_classes() {
+        let set = PatternSet::new(&[
+            PatternSpec::new("[Gg]ems", Position::End, false).into(),
+            PatternSpec::new("X?Y", Position::Start, false).into(),
+            PatternSpec::new("[1-9][1-9][1-9][1-9]", Position::End, false).into(),
+        ])
+        .unwrap();
+
+        assert_eq!(set.find_match("abcGems"), Some(0));
+        assert_eq!(set.find_match("abcgems"), Some(0));
+        assert_eq!(set.find_match("abcGEMS"), None);
+        assert_eq!(set.find_match("XaYbc"), Some(1));
+        assert_eq!(set.find_match("XYbc"), None);
+        assert_eq!(set.find_match("abc2024"), None);
+        assert_eq!(set.find_match("abc2524"), Some(2));
+
+        let expected = (9.0 / 58.0f64).powi(4);
+        let digits = PatternSet::new(&[PatternSpec::new("[1-9][1-9][1-9][1-9]", Position::End, false).into()]);
+        assert!((digits.unwrap().difficulty().probability - expected).abs() < expected * 1e-9);
+
+        assert!(PatternSet::new(&[PatternSpec::new("[ab", Position::End, false).into()]).is_err());
+        assert!(PatternSet::new(&[PatternSpec::new("[]", Position::End, false).into()]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_characters_outside_base58() {
+        let err = PatternSet::new(&[PatternSpec::new("T0kIl", Position::End, false).into()]).unwrap_err();
+        match &err {
+            PatternError::InvalidCharacters { invalid, .. } => {
+                let chars: Vec<char> = invalid.iter().map(|i| i.character).collect();
+                assert_eq!(chars, vec!['0', 'I', 'l']);
+                assert_eq!(invalid[0].suggestions, vec!['o']);
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+        assert!(err.to_string().contains("'0' (try 'o')"));
+
+        // 'o', 'i' and 'L' exist in base58, so folding case makes these usable
+        assert!(PatternSet::new(&[PatternSpec::new("OIl", Position::End, true).into()]).is_ok());
+        assert!(PatternSet::new(&[PatternSpec::new("g0", Position::End, true).into()]).is_err());
+        assert!(PatternSet::new(&[PatternSpec::new("gëm", Position::End, false).into()]).is_err());
+        assert!(PatternSet::new(&[PatternSpec::new("[0O]", Position::End, false).into()]).is_err());
+        let err = PatternSet::new(&[PatternSpec::new("x[0a]", Position::End, false).into()]).unwrap_err();
+        assert!(err.to_string().contains("'0' in [0a] (try 'o')"));
+        assert!(PatternSet::new(&[PatternSpec::new("[a-z]", Position::End, false).into()]).is_ok());
+        assert!(PatternSet::new(&[PatternSpec::new("ab", "at:43".parse().unwrap(), false).into()]).is_err());
+        assert!(matches!(
+            PatternSet::new(&[PatternSpec::new("ab", Position::At(usize::MAX), false).into()]),
+            Err(PatternError::TooLong { .. })
+        ));
+    }
+
+    #[test]
+    fn test_rejects_unknown_position() {
+        assert!("middle".parse::<Position>().is_err());
+        assert!("at:x".parse::<Position>().is_err());
+        assert!(PatternSet::new(&[]).is_err());
+    }
+}
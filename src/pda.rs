@@ -0,0 +1,226 @@
+use anyhow::{anyhow, Result};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_program::pubkey::{Pubkey, MAX_SEEDS, MAX_SEED_LEN};
+
+use crate::base58::MAX_ENCODED_LEN;
+use crate::field::FieldElement;
+use crate::seed::SeedSchedule;
+use crate::{BatchMatch, ChunkBest, PatternSet, SearchStats, CHUNK_SIZE};
+
+/// Appended to the seeds of every program address, private in `solana_program`.
+const PDA_MARKER: &[u8] = b"ProgramDerivedAddress";
+
+/// The fixed part of a PDA search: the program and the seeds around the ground one.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PdaSeeds {
+    pub program_id: Pubkey,
+    /// Seeds every candidate shares.
+    pub seeds: Vec<Vec<u8>>,
+    /// Where the ground seed goes among `seeds`, `seeds.len()` to put it last.
+    pub variable_position: usize,
+}
+
+impl PdaSeeds {
+    pub fn validate(&self) -> Result<()> {
+        if self.variable_position > self.seeds.len() {
+            return Err(anyhow!(
+                "Variable seed position {} is past the {} fixed seeds",
+                self.variable_position,
+                self.seeds.len()
+            ));
+        }
+        // The ground seed and the bump take a place each
+        if self.seeds.len() + 2 > MAX_SEEDS {
+            return Err(anyhow!("A PDA takes at most {} fixed seeds besides the ground one", MAX_SEEDS - 2));
+        }
+        if let Some(seed) = self.seeds.iter().find(|seed| seed.len() > MAX_SEED_LEN) {
+            return Err(anyhow!("PDA seeds are at most {} bytes, got {}", MAX_SEED_LEN, seed.len()));
+        }
+        Ok(())
+    }
+
+    /// Every seed in order, with `variable` in its place.
+    pub fn with_variable<'a>(&'a self, variable: &'a [u8]) -> Vec<&'a [u8]> {
+        let mut seeds: Vec<&[u8]> = self.seeds.iter().map(Vec::as_slice).collect();
+        seeds.insert(self.variable_position, variable);
+        seeds
+    }
+}
+
+/// The seeds and canonical bump of a PDA match.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PdaAddress {
+    pub program_id: Pubkey,
+    /// Every seed in order, the ground one included, without the bump.
+    pub seeds: Vec<Vec<u8>>,
+    pub bump: u8,
+}
+
+impl PdaAddress {
+    /// Derives the address again with `create_program_address`.
+    pub fn address(&self) -> Result<Pubkey> {
+        let bump = [self.bump];
+        let mut seeds: Vec<&[u8]> = self.seeds.iter().map(Vec::as_slice).collect();
+        seeds.push(&bump);
+        Pubkey::create_program_address(&seeds, &self.program_id).map_err(|e| anyhow!("Invalid PDA: {}", e))
+    }
+}
+
+/// `find_program_address` on top of the hashed seeds: the first bump from 255 down
+/// whose hash is off the curve. `None` in the unheard of case that none is.
+pub(crate) fn canonical_address(seeds_state: &Sha256, program_id: &Pubkey) -> Option<[u8; 32]> {
+    (0..=u8::MAX).rev().find_map(|bump| {
+        let address: [u8; 32] = seeds_state
+            .clone()
+            .chain_update([bump])
+            .chain_update(program_id)
+            .chain_update(PDA_MARKER)
+            .finalize()
+            .into();
+        FieldElement::decompress(&address).is_none().then_some(address)
+    })
+}
+
+/// Grinds the variable seed of a PDA: the seeds of `schedule` between the fixed
+/// seeds, each with its canonical bump like `find_program_address`.
+pub(crate) struct PdaGrinder<'a> {
+    pda: PdaSeeds,
+    schedule: &'a SeedSchedule,
+    /// The fixed seeds before the variable one, hashed once.
+    prefix_state: Sha256,
+}
+
+impl<'a> PdaGrinder<'a> {
+    pub(crate) fn new(pda: PdaSeeds, schedule: &'a SeedSchedule) -> Self {
+        let mut prefix_state = Sha256::new();
+        for seed in &pda.seeds[..pda.variable_position] {
+            prefix_state.update(seed);
+        }
+        Self {
+            pda,
+            schedule,
+            prefix_state,
+        }
+    }
+
+    pub(crate) fn program_id(&self) -> Pubkey {
+        self.pda.program_id
+    }
+
+    pub(crate) fn capacity(&self) -> Option<u64> {
+        self.schedule.capacity()
+    }
+
+    pub(crate) fn seed(&self, index: u64) -> String {
+        self.schedule.cursor(index).to_string()
+    }
+
+    /// The PDA of the seed at `index` and its canonical bump.
+    pub(crate) fn address(&self, index: u64) -> PdaAddress {
+        let seed = self.schedule.cursor(index).seed().to_vec();
+        let seeds = self.pda.with_variable(&seed);
+        let (_, bump) = Pubkey::find_program_address(&seeds, &self.pda.program_id);
+        PdaAddress {
+            program_id: self.pda.program_id,
+            seeds: seeds.iter().map(|seed| seed.to_vec()).collect(),
+            bump,
+        }
+    }
+
+    /// Grinds the seeds `start..start + count`. Like `search_batch`, the lowest
+    /// matching index wins and ends the batch.
+    pub(crate) fn search_batch(
+        &self,
+        start: u64,
+        count: u64,
+        patterns: &PatternSet,
+        stats: &SearchStats,
+    ) -> Option<BatchMatch> {
+        let result = (0..count.div_ceil(CHUNK_SIZE as u64))
+            .into_par_iter()
+            .find_map_first(|chunk| {
+                let chunk_start = start + chunk * CHUNK_SIZE as u64;
+                let chunk_end = (chunk_start + CHUNK_SIZE as u64).min(start + count);
+                let mut cursor = self.schedule.cursor(chunk_start);
+                let mut encoded = [0u8; MAX_ENCODED_LEN];
+                let mut best = ChunkBest::new(stats);
+
+                for index in chunk_start..chunk_end {
+                    let mut seeds_state = self.prefix_state.clone();
+                    seeds_state.update(cursor.seed());
+                    for seed in &self.pda.seeds[self.pda.variable_position..] {
+                        seeds_state.update(seed);
+                    }
+                    cursor.advance();
+                    let Some(address) = canonical_address(&seeds_state, &self.pda.program_id) else {
+                        continue;
+                    };
+
+                    if let Some(pattern) = patterns.find_match_address(&address, &mut encoded) {
+                        return Some(BatchMatch {
+                            attempt_index: index,
+                            owner: 0,
+                            address: Pubkey::new_from_array(address),
+                            pattern,
+                        });
+                    }
+
+                    best.offer(patterns, &address, index, 0);
+                }
+
+                best.finish();
+                None
+            });
+
+        let attempts = result.map_or(count, |found| found.attempt_index - start + 1);
+        stats.attempts.fetch_add(attempts, std::sync::atomic::Ordering::Relaxed);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PatternSpec, Position, SeedFormat};
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn test_pda_search_matches_find_program_address() {
+        let pda = PdaSeeds {
+            program_id: Pubkey::new_unique(),
+            seeds: vec![b"mint".to_vec(), Pubkey::new_unique().to_bytes().to_vec()],
+            variable_position: 1,
+        };
+        pda.validate().unwrap();
+        let format = SeedFormat {
+            len: 12,
+            ..SeedFormat::default()
+        };
+        let schedule = SeedSchedule::random(&format).unwrap();
+        let grinder = PdaGrinder::new(pda.clone(), &schedule);
+        let patterns = PatternSet::new(&[PatternSpec::new("a", Position::End, false).into()]).unwrap();
+        let stats = SearchStats::new();
+
+        let found = grinder.search_batch(0, 2000, &patterns, &stats).unwrap();
+        assert_eq!(stats.attempts.load(Ordering::Relaxed), found.attempt_index + 1);
+        let address = grinder.address(found.attempt_index);
+        assert_eq!(address.address().unwrap(), found.address);
+        assert_eq!(address.seeds[1], schedule.cursor(found.attempt_index).seed());
+
+        for index in 0..=found.attempt_index {
+            let seed = schedule.cursor(index).seed().to_vec();
+            let (expected, _) = Pubkey::find_program_address(&pda.with_variable(&seed), &pda.program_id);
+            assert_eq!(index == found.attempt_index, expected.to_string().ends_with('a'));
+        }
+
+        for invalid in [
+            PdaSeeds { variable_position: 3, ..pda.clone() },
+            PdaSeeds { seeds: vec![vec![0; 33]], variable_position: 0, ..pda.clone() },
+            PdaSeeds { seeds: vec![vec![]; 15], variable_position: 0, ..pda.clone() },
+        ] {
+            assert!(invalid.validate().is_err());
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use crate::pattern::{CharClass, ADDRESS_LEN, BASE58_ALPHABET};
+
+/// Longest prefix turned into ranges: every spelling must fit a u64 digit value.
+const MAX_PREFIX_LEN: usize = 10;
+
+/// Cap on the spellings expanded into ranges. Longer or wider prefixes only have
+/// their leading characters checked numerically and are confirmed after encoding.
+const MAX_VARIANTS: usize = 4096;
+
+/// An unsigned integer wide enough for 58^44. Limbs are big-endian so the derived
+/// ordering is the numeric one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Wide([u64; 5]);
+
+impl Wide {
+    fn from_u64(value: u64) -> Self {
+        Self([0, 0, 0, 0, value])
+    }
+
+    fn pow2(bits: u32) -> Self {
+        let mut limbs = [0u64; 5];
+        limbs[4 - (bits / 64) as usize] = 1 << (bits % 64);
+        Self(limbs)
+    }
+
+    fn mul_pow58(mut self, exponent: usize) -> Self {
+        for _ in 0..exponent {
+            let mut carry = 0u128;
+            for limb in self.0.iter_mut().rev() {
+                let cur = *limb as u128 * BASE58_ALPHABET.len() as u128 + carry;
+                *limb = cur as u64;
+                carry = cur >> 64;
+            }
+        }
+        self
+    }
+
+    fn pow58(exponent: usize) -> Self {
+        Self::from_u64(1).mul_pow58(exponent)
+    }
+
+    fn sub_one(mut self) -> Self {
+        for limb in self.0.iter_mut().rev() {
+            let (value, borrow) = limb.overflowing_sub(1);
+            *limb = value;
+            if !borrow {
+                break;
+            }
+        }
+        self
+    }
+
+    /// Big-endian bytes of a value below 2^256.
+    fn to_be_bytes(self) -> [u8; 32] {
+        debug_assert_eq!(self.0[0], 0);
+        let mut bytes = [0u8; 32];
+        for (chunk, limb) in bytes.chunks_exact_mut(8).zip(&self.0[1..]) {
+            chunk.copy_from_slice(&limb.to_be_bytes());
+        }
+        bytes
+    }
+}
+
+/// A start pattern as the sorted, disjoint ranges of 256-bit address values whose
+/// base58 encoding begins with one of its spellings. Encodings are 43 or 44
+/// characters long, so each spelling covers one range per possible length.
+#[derive(Debug, Clone)]
+pub(crate) struct PrefixRanges {
+    /// Inclusive big-endian bounds.
+    ranges: Vec<([u8; 32], [u8; 32])>,
+}
+
+impl PrefixRanges {
+    /// Ranges for as many leading characters of a start pattern as can be expanded.
+    pub(crate) fn new(classes: &[CharClass]) -> Option<Self> {
+        Self::exact(&classes[..checkable_len(classes)])
+    }
+
+    /// Ranges for each prefix of the expandable part of a start pattern: the
+    /// address matches the first `j + 1` characters iff `levels[j]` contains it.
+    pub(crate) fn levels(classes: &[CharClass]) -> Vec<Self> {
+        (1..=checkable_len(classes))
+            .filter_map(|len| Self::exact(&classes[..len]))
+            .collect()
+    }
+
+    fn exact(classes: &[CharClass]) -> Option<Self> {
+        // Leading zero bytes encode as '1' and don't map to a single range
+        if classes.first()?.contains(BASE58_ALPHABET[0]) {
+            return None;
+        }
+
+        // Trailing wildcards never rule an address out
+        let len = classes
+            .iter()
+            .rposition(|class| class.base58_count() < BASE58_ALPHABET.len())
+            .map_or(0, |i| i + 1);
+
+        let mut prefixes = vec![0u64];
+        for class in &classes[..len] {
+            let digits: Vec<u64> = (0..BASE58_ALPHABET.len() as u64)
+                .filter(|&d| class.contains(BASE58_ALPHABET[d as usize]))
+                .collect();
+            prefixes = prefixes
+                .iter()
+                .flat_map(|&p| digits.iter().map(move |&d| p * BASE58_ALPHABET.len() as u64 + d))
+                .collect();
+        }
+
+        // Values below 2^248 start with a zero byte
+        let min = Wide::pow2(248);
+        let max = Wide::pow2(256);
+        let mut ranges: Vec<(Wide, Wide)> = Vec::new();
+        for digits in len..=ADDRESS_LEN {
+            let lo_bound = Wide::pow58(digits - 1).max(min);
+            let hi_bound = Wide::pow58(digits).min(max);
+            if lo_bound >= hi_bound {
+                continue;
+            }
+            for &prefix in &prefixes {
+                let lo = Wide::from_u64(prefix).mul_pow58(digits - len).max(lo_bound);
+                let hi = Wide::from_u64(prefix + 1).mul_pow58(digits - len).min(hi_bound);
+                if lo < hi {
+                    ranges.push((lo, hi));
+                }
+            }
+        }
+
+        ranges.sort_unstable();
+        let mut merged: Vec<(Wide, Wide)> = Vec::with_capacity(ranges.len());
+        for (lo, hi) in ranges {
+            match merged.last_mut() {
+                Some(last) if lo <= last.1 => last.1 = last.1.max(hi),
+                _ => merged.push((lo, hi)),
+            }
+        }
+
+        Some(Self {
+            ranges: merged
+                .into_iter()
+                .map(|(lo, hi)| (lo.to_be_bytes(), hi.sub_one().to_be_bytes()))
+                .collect(),
+        })
+    }
+
+    pub(crate) fn contains(&self, address: &[u8; 32]) -> bool {
+        let i = self.ranges.partition_point(|(_, hi)| hi < address);
+        self.ranges.get(i).is_some_and(|(lo, _)| lo <= address)
+    }
+}
+
+/// Number of leading classes whose spellings can be expanded into ranges.
+fn checkable_len(classes: &[CharClass]) -> usize {
+    let mut len = 0;
+    let mut variants = 1;
+    for class in classes.iter().take(MAX_PREFIX_LEN) {
+        variants *= class.base58_count();
+        if variants > MAX_VARIANTS {
+            break;
+        }
+        len += 1;
+    }
+    len
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::{Callback, PartialMatch};
+
+/// What a search is grinding on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Backend {
+    Cpu { threads: usize },
+    Gpu,
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Backend::Cpu { threads } => write!(f, "CPU ({} threads)", threads),
+            Backend::Gpu => write!(f, "GPU"),
+        }
+    }
+}
+
+/// A snapshot of a running search, delivered to `SearchOptions::on_progress`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressEvent {
+    pub backend: Backend,
+    /// Attempts so far, including those of a resumed checkpoint.
+    pub attempts: u64,
+    /// Seconds spent so far, including those of a resumed checkpoint.
+    pub elapsed: f64,
+    /// Attempts/s since the previous event.
+    pub rate: f64,
+    /// Attempts/s over the current run.
+    pub average_rate: f64,
+    /// Probability of having found a match by now.
+    pub chance: f64,
+    /// Expected seconds until a match at the average rate.
+    pub eta: f64,
+    /// The closest candidate so far, see `SearchOptions::track_best`.
+    pub best_match: Option<PartialMatch>,
+}
+
+/// Receives progress events on the search's reporting thread.
+pub type ProgressCallback = Callback<ProgressEvent>;
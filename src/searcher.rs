@@ -0,0 +1,640 @@
+use anyhow::{anyhow, Result};
+use futures::{
+    channel::{mpsc, oneshot},
+    executor::block_on,
+    SinkExt,
+};
+use log::{info, warn};
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::checkpoint::CheckpointWriter;
+use crate::metal::MetalDevice;
+use crate::stream::{CancelOnDrop, LatestProgress, SearchEvent, SearchStream};
+use crate::{
+    run_search, start_search, Backend, CancelHandle, Checkpoint, CompoundPattern, Grinder, LongestMatch,
+    MatchCallback, MatchCollection, PatternSet, Progress, ProgressCallback, SearchBase, SearchOptions,
+    SearchOutcome, SearchStart, VanityAddressResult,
+};
+
+/// Matches a stream holds before its search waits for the consumer.
+const STREAM_BUFFER: usize = 64;
+
+/// Runs searches on a thread pool of its own rather than rayon's global one, so a
+/// process can run any number of them: one after another on the same searcher, or
+/// side by side on separate searchers with separate thread budgets. Each search
+/// drives its pool from a dedicated thread, so awaiting one never blocks the
+/// executor. Clones share the pool, GPU and totals.
+#[derive(Clone)]
+pub struct VanitySearcher(Arc<Shared>);
+
+struct Shared {
+    pool: rayon::ThreadPool,
+    threads: usize,
+    metal_device: Option<MetalDevice>,
+    attempts: AtomicU64,
+    searches: AtomicU64,
+}
+
+impl VanitySearcher {
+    /// A searcher grinding on `threads` CPU threads (default: num_cpus), and on the
+    /// Metal GPU if `use_gpu` and one is available.
+    pub fn new(threads: Option<usize>, use_gpu: bool) -> Result<Self> {
+        let threads = threads.unwrap_or_else(num_cpus::get);
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|index| format!("vanity-search-{}", index))
+            .build()?;
+
+        #[cfg(feature = "gpu")]
+        let metal_device = if use_gpu {
+            match MetalDevice::new() {
+                Ok(device) => {
+                    info!("Metal GPU acceleration enabled");
+                    Some(device)
+                }
+                Err(e) => {
+                    warn!("Failed to initialize Metal GPU: {}", e);
+                    warn!("Falling back to CPU");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        #[cfg(not(feature = "gpu"))]
+        let metal_device: Option<MetalDevice> = {
+            if use_gpu {
+                warn!("Built without GPU support, searching on the CPU");
+            }
+            None
+        };
+
+        Ok(Self(Arc::new(Shared {
+            pool,
+            threads,
+            metal_device,
+            attempts: AtomicU64::new(0),
+            searches: AtomicU64::new(0),
+        })))
+    }
+
+    /// The searcher of the free search functions, one per call.
+    pub(crate) fn for_options(options: &SearchOptions) -> Result<Self> {
+        Self::new(options.threads, options.use_gpu)
+    }
+
+    /// The GPU if the searcher has one, its CPU threads otherwise. Searches that
+    /// can't run on the GPU always grind on the CPU.
+    pub fn backend(&self) -> Backend {
+        match self.0.metal_device {
+            Some(_) => Backend::Gpu,
+            None => Backend::Cpu { threads: self.0.threads },
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.0.threads
+    }
+
+    /// Addresses tried by the searches that ran on this searcher.
+    pub fn attempts(&self) -> u64 {
+        self.0.attempts.load(Ordering::Relaxed)
+    }
+
+    /// Number of searches that ran to an end on this searcher.
+    pub fn searches(&self) -> u64 {
+        self.0.searches.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn pool(&self) -> &rayon::ThreadPool {
+        &self.0.pool
+    }
+
+    pub(crate) fn metal_device(&self) -> Option<&MetalDevice> {
+        self.0.metal_device.as_ref()
+    }
+
+    pub(crate) fn record_search(&self, attempts: u64) {
+        self.0.attempts.fetch_add(attempts, Ordering::Relaxed);
+        self.0.searches.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Runs `search` on a thread of its own and waits for it without blocking the
+    /// executor. Dropping the future before the search ends cancels it.
+    async fn off_thread<T: Send + 'static>(
+        &self,
+        cancel: CancelHandle,
+        search: impl FnOnce(&VanitySearcher) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let searcher = self.clone();
+        let (sender, receiver) = oneshot::channel();
+        std::thread::Builder::new()
+            .name("vanity-search".to_string())
+            .spawn(move || {
+                let _ = sender.send(search(&searcher));
+            })?;
+
+        let cancel_on_drop = CancelOnDrop::new(cancel);
+        let result = receiver.await.map_err(|_| anyhow!("The search thread panicked"))?;
+        cancel_on_drop.disarm();
+        result
+    }
+
+    /// Searches for an address matching any of `patterns`, configured by `options`.
+    /// Without a cancel handle or budget the search only ends on a match.
+    pub async fn find(&self, patterns: &[CompoundPattern], options: &SearchOptions) -> Result<SearchOutcome> {
+        Ok(self.collect(patterns, 1, options).await?.into_outcome())
+    }
+
+    /// Keeps searching until `count` distinct addresses match any of `patterns`, all
+    /// derived from the same base key. Each match goes to `options.on_match` as
+    /// soon as it is found. A cancel handle or budget can end the search early with
+    /// the matches collected so far.
+    pub async fn collect(
+        &self,
+        patterns: &[CompoundPattern],
+        count: usize,
+        options: &SearchOptions,
+    ) -> Result<MatchCollection> {
+        if count == 0 {
+            return Err(anyhow!("Match count must be at least 1"));
+        }
+        let patterns = PatternSet::new(patterns)?;
+        let (options, cancel) = with_cancel(options);
+        self.off_thread(cancel, move |searcher| {
+            let mut start = start_search(&patterns, &options)?;
+            run_search(searcher, &patterns, count, &options, &mut start)
+        })
+        .await
+    }
+
+    /// Like `collect`, but streams the search's progress and matches as they come.
+    /// The stream ends after the last match, with a `Stopped` event when the search
+    /// stops short of `count` matches, or with an error. `options.on_progress` and
+    /// `options.on_match` still get every event. Progress events the stream isn't
+    /// polled for merge into the newest one, and the last one before the end has the
+    /// final count. Matches are never dropped, the search waits while unread ones
+    /// pile up. Dropping the stream cancels the search.
+    pub fn stream(
+        &self,
+        patterns: &[CompoundPattern],
+        count: usize,
+        options: &SearchOptions,
+    ) -> Result<SearchStream> {
+        if count == 0 {
+            return Err(anyhow!("Match count must be at least 1"));
+        }
+        let patterns = PatternSet::new(patterns)?;
+        let (mut options, cancel) = with_cancel(options);
+        // Progress events merge into the newest one. Matches never get dropped, once
+        // the channel is full the search waits for the consumer to catch up.
+        let progress = Arc::new(LatestProgress::default());
+        let (mut sender, events) = mpsc::channel(count.min(STREAM_BUFFER));
+
+        let (progress_slot, on_progress) = (progress.clone(), options.on_progress.take());
+        options.on_progress = Some(ProgressCallback::new(move |event| {
+            if let Some(callback) = &on_progress {
+                callback.call(event);
+            }
+            progress_slot.set(event.clone());
+        }));
+        let (match_sender, on_match) = (Mutex::new(sender.clone()), options.on_match.take());
+        options.on_match = Some(MatchCallback::new(move |result| {
+            if let Some(callback) = &on_match {
+                callback.call(result);
+            }
+            let mut match_sender = match_sender.lock().expect("match sender lock poisoned");
+            let _ = block_on(match_sender.send(Ok(SearchEvent::Match(result.clone()))));
+        }));
+
+        let searcher = self.clone();
+        std::thread::Builder::new()
+            .name("vanity-search".to_string())
+            .spawn(move || {
+                let collection = start_search(&patterns, &options)
+                    .and_then(|mut start| run_search(&searcher, &patterns, count, &options, &mut start));
+                let last = match collection {
+                    Ok(collection) => collection.incomplete.map(|summary| Ok(SearchEvent::Stopped(summary))),
+                    Err(e) => Some(Err(e)),
+                };
+                if let Some(last) = last {
+                    let _ = block_on(sender.send(last));
+                }
+            })?;
+
+        Ok(SearchStream::new(progress, events, cancel))
+    }
+
+    /// Searches until `options` runs out of budget or is cancelled and returns the
+    /// candidate that came closest, e.g. the address ending in "gems" when "gemstone"
+    /// at the end is out of reach. A full match ends the search early.
+    pub async fn find_longest_match(
+        &self,
+        patterns: &[CompoundPattern],
+        options: &SearchOptions,
+    ) -> Result<LongestMatch> {
+        if options.cancel.is_none() && options.timeout.is_none() && options.max_attempts.is_none() {
+            return Err(anyhow!(
+                "A best effort search needs a timeout, an attempt budget or a cancel handle"
+            ));
+        }
+        let patterns = PatternSet::new(patterns)?;
+
+        let (mut options, cancel) = with_cancel(options);
+        options.track_best = true;
+        if options.use_gpu {
+            // Only the CPU search keeps track of the closest candidate
+            warn!("GPU search can't track the closest candidate, falling back to CPU");
+            options.use_gpu = false;
+        }
+
+        self.off_thread(cancel, move |searcher| {
+            let mut start = start_search(&patterns, &options)?;
+            let outcome = run_search(searcher, &patterns, 1, &options, &mut start)?.into_outcome();
+
+            match outcome {
+                SearchOutcome::Found(result) => {
+                    let pattern_chars = patterns.pattern_chars(result.matched_pattern);
+                    Ok(LongestMatch {
+                        result,
+                        matched_chars: pattern_chars,
+                        pattern_chars,
+                        stopped: None,
+                    })
+                }
+                SearchOutcome::NotFound(summary) => {
+                    let best = summary.best_match.ok_or_else(|| {
+                        anyhow!("No candidate matched a single character: {}", summary.reason)
+                    })?;
+                    info!(
+                        "Closest candidate matches {} of {} characters",
+                        best.matched_chars, best.pattern_chars
+                    );
+                    let grinder = Grinder::new(&options, &start.base, &start.schedule, &options.owner_programs())?;
+                    let (base_pubkey, keypair_json) = grinder.keys(Some(best.attempt_index));
+
+                    Ok(LongestMatch {
+                        result: VanityAddressResult {
+                            mode: options.mode,
+                            base_pubkey: base_pubkey.to_string(),
+                            seed: best.seed,
+                            token_address: best.token_address,
+                            owner: best.owner,
+                            keypair_json,
+                            time_taken: summary.time_taken,
+                            attempts: summary.attempts,
+                            matched_pattern: best.matched_pattern,
+                            attempt_index: Some(best.attempt_index),
+                            pda: grinder.pda(Some(best.attempt_index)),
+                            mint: grinder.mint(),
+                        },
+                        matched_chars: best.matched_chars,
+                        pattern_chars: best.pattern_chars,
+                        stopped: Some(summary.reason),
+                    })
+                }
+            }
+        })
+        .await
+    }
+
+    /// Continues the search saved in `checkpoint`, skipping every seed it already
+    /// covered, until it has the matches it still had to find. `options.checkpoint`
+    /// supplies where to keep saving and the passphrase, which checkpoints of
+    /// pubkey-only searches don't need.
+    pub async fn resume(&self, checkpoint: Checkpoint, options: &SearchOptions) -> Result<MatchCollection> {
+        let checkpoint_options = options
+            .checkpoint
+            .clone()
+            .ok_or_else(|| anyhow!("Resuming a search requires checkpoint options"))?;
+        let patterns = PatternSet::new(&checkpoint.patterns)?;
+        let match_count = checkpoint.match_count;
+        if match_count == 0 {
+            return Err(anyhow!("Checkpoint has no matches left to find"));
+        }
+
+        // The mode, owners, PDA seeds and ATA mint are part of what the checkpoint covered
+        let (options, cancel) = with_cancel(&SearchOptions {
+            mode: checkpoint.mode,
+            owners: checkpoint.owners.clone(),
+            pda: checkpoint.pda.clone(),
+            ata_mint: checkpoint.ata_mint,
+            track_best: checkpoint.track_best,
+            ..options.clone()
+        });
+
+        // Decrypting the base keypair takes a while too, it runs on the search thread
+        self.off_thread(cancel, move |searcher| {
+            let base = if checkpoint.encrypted_base_keypair.is_some() {
+                SearchBase::from_keypair(Arc::new(
+                    checkpoint.decrypt_base_keypair(&checkpoint_options.passphrase)?,
+                ))
+            } else {
+                SearchBase::from_pubkey(checkpoint.base_pubkey.parse()?)
+            };
+            let schedule = checkpoint.seed_schedule()?;
+            let progress = Progress {
+                next_index: checkpoint.next_index,
+                attempts: checkpoint.attempts,
+                elapsed: Duration::from_secs_f64(checkpoint.elapsed_secs),
+            };
+            info!(
+                "Resuming search at attempt {} after {:.0}s, {} matches to go",
+                progress.next_index,
+                progress.elapsed.as_secs_f64(),
+                match_count
+            );
+
+            let mut start = SearchStart {
+                base,
+                schedule,
+                progress,
+                checkpoint: Some(CheckpointWriter::resume(&checkpoint_options, checkpoint)),
+            };
+            run_search(searcher, &patterns, match_count, &options, &mut start)
+        })
+        .await
+    }
+}
+
+/// `options` with a cancel handle, its own if it has one, and the handle. Searches
+/// the caller can stop early track their closest candidate, decided before the
+/// handle added here makes every search stoppable.
+fn with_cancel(options: &SearchOptions) -> (SearchOptions, CancelHandle) {
+    let mut options = options.clone();
+    options.track_best |= options.cancel.is_some() || options.timeout.is_some() || options.max_attempts.is_some();
+    let cancel = options.cancel.get_or_insert_with(CancelHandle::new).clone();
+    (options, cancel)
+}
+
+impl fmt::Debug for VanitySearcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VanitySearcher")
+            .field("backend", &self.backend())
+            .field("attempts", &self.attempts())
+            .field("searches", &self.searches())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find_vanity_address_with_options, BaseKey, CheckpointOptions, PatternSpec, Position, StopReason};
+    use solana_program::pubkey::Pubkey;
+    use futures::StreamExt;
+    use std::time::Instant;
+
+    fn patterns() -> Vec<CompoundPattern> {
+        vec![PatternSpec::new("a", Position::End, false).into()]
+    }
+
+    #[tokio::test]
+    async fn test_searches_run_repeatedly_in_one_process() {
+        // Used to fail the second time on building the global thread pool
+        for _ in 0..2 {
+            find_vanity_address_with_options(&patterns(), &SearchOptions::default())
+                .await
+                .unwrap()
+                .into_found()
+                .unwrap();
+        }
+
+        let searcher = VanitySearcher::new(Some(1), false).unwrap();
+        let first = searcher.collect(&patterns(), 2, &SearchOptions::default()).await.unwrap();
+        let second = searcher.find(&patterns(), &SearchOptions::default()).await.unwrap().into_found().unwrap();
+        assert_eq!(first.matches.len(), 2);
+        assert_eq!(searcher.searches(), 2);
+        assert_eq!(searcher.attempts(), first.matches[1].attempts + second.attempts);
+        assert_eq!(searcher.backend(), Backend::Cpu { threads: 1 });
+    }
+
+    #[test]
+    fn test_searchers_run_side_by_side() {
+        let searchers = [VanitySearcher::new(Some(1), false).unwrap(), VanitySearcher::new(Some(2), false).unwrap()];
+        std::thread::scope(|scope| {
+            for searcher in &searchers {
+                scope.spawn(move || {
+                    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+                    runtime.block_on(searcher.collect(&patterns(), 3, &SearchOptions::default())).unwrap()
+                });
+            }
+        });
+        for searcher in &searchers {
+            assert_eq!(searcher.searches(), 1);
+            assert!(searcher.attempts() >= 3);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_huge_count_collects_within_budget() {
+        // Nothing is reserved up front for matches the budget never reaches
+        let searcher = VanitySearcher::new(Some(1), false).unwrap();
+        let options = SearchOptions {
+            max_attempts: Some(1000),
+            ..SearchOptions::default()
+        };
+        let collection = searcher.collect(&patterns(), usize::MAX, &options).await.unwrap();
+        assert!(!collection.matches.is_empty());
+        assert_eq!(collection.incomplete.unwrap().reason, StopReason::MaxAttempts);
+    }
+
+    #[tokio::test]
+    async fn test_budgeted_search_reports_closest_candidate() {
+        let searcher = VanitySearcher::new(Some(1), false).unwrap();
+        let options = SearchOptions {
+            max_attempts: Some(1000),
+            ..SearchOptions::default()
+        };
+        let impossible = [PatternSpec::new("zzzzzzzzzz", Position::End, false).into()];
+        let summary = searcher.collect(&impossible, 1, &options).await.unwrap().incomplete.unwrap();
+        assert!(summary.best_match.unwrap().token_address.ends_with('z'));
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_is_removed_once_the_search_completes() {
+        let searcher = VanitySearcher::new(Some(1), false).unwrap();
+        let path = std::env::temp_dir().join(format!("token22-vanity-{}.json", rand::random::<u64>()));
+        let saved = path.clone();
+        let options = SearchOptions {
+            // Pubkey-only, so the checkpoint skips the slow key derivation
+            base_key: BaseKey::Pubkey(Pubkey::new_unique()),
+            checkpoint: Some(CheckpointOptions {
+                path: path.clone(),
+                passphrase: String::new(),
+                interval: Duration::ZERO,
+            }),
+            on_match: Some(MatchCallback::new(move |_: &VanityAddressResult| assert!(saved.exists()))),
+            ..SearchOptions::default()
+        };
+        searcher.collect(&patterns(), 2, &options).await.unwrap();
+        assert!(!path.exists());
+
+        // A search that stops early keeps it to be resumed
+        let options = SearchOptions {
+            max_attempts: Some(1000),
+            on_match: None,
+            ..options
+        };
+        let impossible = [PatternSpec::new("zzzzzzzzzz", Position::End, false).into()];
+        searcher.collect(&impossible, 1, &options).await.unwrap().incomplete.unwrap();
+        assert_eq!(Checkpoint::load(&path).unwrap().attempts, 1000);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_resume_collects_the_remaining_matches() {
+        let searcher = VanitySearcher::new(Some(1), false).unwrap();
+        let path = std::env::temp_dir().join(format!("token22-vanity-{}.json", rand::random::<u64>()));
+        let checkpoint_options = CheckpointOptions {
+            path: path.clone(),
+            passphrase: String::new(),
+            interval: Duration::ZERO,
+        };
+        let options = SearchOptions {
+            base_key: BaseKey::Pubkey(Pubkey::new_unique()),
+            checkpoint: Some(checkpoint_options.clone()),
+            max_attempts: Some(2),
+            track_best: true,
+            ..SearchOptions::default()
+        };
+        // Every address matches, so the budget stops the search after two of five
+        let anything = [PatternSpec::new("?", Position::End, false).into()];
+        let collection = searcher.collect(&anything, 5, &options).await.unwrap();
+        assert_eq!(collection.matches.len(), 2);
+
+        let checkpoint = Checkpoint::load(&path).unwrap();
+        assert_eq!(checkpoint.match_count, 3);
+        assert!(checkpoint.track_best);
+        let options = SearchOptions {
+            checkpoint: Some(checkpoint_options),
+            ..SearchOptions::default()
+        };
+        let resumed = searcher.resume(checkpoint, &options).await.unwrap();
+        assert!(resumed.incomplete.is_none());
+        assert_eq!(resumed.matches.len(), 3);
+        assert_eq!(resumed.matches[0].attempt_index, Some(2));
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_awaiting_a_search_leaves_the_executor_free() {
+        // The default test runtime has a single thread. A blocking search would hold
+        // it through the match callback and the ticker would only run afterwards.
+        let searcher = VanitySearcher::new(Some(1), false).unwrap();
+        let options = SearchOptions {
+            on_match: Some(MatchCallback::new(|_: &VanityAddressResult| std::thread::sleep(Duration::from_millis(300)))),
+            ..SearchOptions::default()
+        };
+
+        let start = Instant::now();
+        let ticker = async {
+            for _ in 0..10 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            start.elapsed()
+        };
+        let search = async {
+            let outcome = searcher.find(&patterns(), &options).await;
+            (outcome, start.elapsed())
+        };
+        let (ticked, (outcome, searched)) = tokio::join!(ticker, search);
+        outcome.unwrap().into_found().unwrap();
+        assert!(ticked < searched);
+    }
+
+    #[tokio::test]
+    async fn test_stream_yields_progress_and_matches() {
+        let searcher = VanitySearcher::new(Some(1), false).unwrap();
+        let options = SearchOptions {
+            progress_interval: Some(Duration::from_millis(1)),
+            ..SearchOptions::default()
+        };
+        let events: Vec<SearchEvent> = searcher
+            .stream(&[PatternSpec::new("aa", Position::End, false).into()], 3, &options)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        let matches: Vec<&VanityAddressResult> = events
+            .iter()
+            .filter_map(|event| match event {
+                SearchEvent::Match(result) => Some(result),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(matches.len(), 3);
+        assert!(matches.iter().all(|result| result.token_address.ends_with("aa")));
+        // The search may well finish before the reporter ticks, the final count still comes
+        let progress = events.iter().filter_map(|event| match event {
+            SearchEvent::Progress(progress) => Some(progress.attempts),
+            _ => None,
+        });
+        assert_eq!(progress.max(), Some(matches[2].attempts));
+        assert!(!events.iter().any(|event| matches!(event, SearchEvent::Stopped(_))));
+
+        // A search stopping short ends its stream with the summary, however many
+        // matches it asked for
+        let options = SearchOptions {
+            max_attempts: Some(1000),
+            ..SearchOptions::default()
+        };
+        let impossible = vec![PatternSpec::new("zzzzzzzzzz", Position::End, false).into()];
+        let mut stream = searcher.stream(&impossible, usize::MAX, &options).unwrap();
+        let mut last = None;
+        while let Some(event) = stream.next().await {
+            last = Some(event.unwrap());
+        }
+        assert!(matches!(last, Some(SearchEvent::Stopped(summary)) if summary.reason == StopReason::MaxAttempts));
+    }
+
+    #[tokio::test]
+    async fn test_unread_progress_events_merge() {
+        let searcher = VanitySearcher::new(Some(1), false).unwrap();
+        let options = SearchOptions {
+            progress_interval: Some(Duration::from_millis(1)),
+            max_attempts: Some(50_000),
+            ..SearchOptions::default()
+        };
+        let impossible = vec![PatternSpec::new("zzzzzzzzzz", Position::End, false).into()];
+        let stream = searcher.stream(&impossible, 1, &options).unwrap();
+
+        // Nobody polls while the search runs, only its newest progress event is kept
+        let start = Instant::now();
+        while searcher.searches() == 0 {
+            assert!(start.elapsed() < Duration::from_secs(30), "search kept running");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let events: Vec<SearchEvent> = stream.map(Result::unwrap).collect().await;
+        assert!(events.len() <= 2);
+        assert!(matches!(events.last(), Some(SearchEvent::Stopped(_))));
+    }
+
+    #[tokio::test]
+    async fn test_dropping_the_stream_cancels_the_search() {
+        let searcher = VanitySearcher::new(Some(1), false).unwrap();
+        let impossible = vec![PatternSpec::new("zzzzzzzzzz", Position::End, false).into()];
+        let cancel = CancelHandle::new();
+        let options = SearchOptions {
+            cancel: Some(cancel.clone()),
+            ..SearchOptions::default()
+        };
+        drop(searcher.stream(&impossible, 1, &options).unwrap());
+        assert!(cancel.is_cancelled());
+
+        let start = Instant::now();
+        while searcher.searches() == 0 {
+            assert!(start.elapsed() < Duration::from_secs(10), "search kept running");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+}
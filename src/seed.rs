@@ -0,0 +1,333 @@
+use anyhow::{anyhow, Result};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_program::pubkey::MAX_SEED_LEN;
+use solana_sdk::signature::{keypair_from_seed, Keypair};
+use std::{fmt, str::FromStr};
+
+use crate::pattern::BASE58_ALPHABET;
+use crate::CHARSET;
+
+const HEX_CHARS: &[u8] = b"0123456789abcdef";
+
+/// Keep the base key and the salt derived from one master seed unrelated.
+const BASE_KEY_TAG: &[u8] = b"token22-vanity base key";
+const SALT_TAG: &[u8] = b"token22-vanity seed salt";
+
+/// Salt a search on a given base key needs, so runs sharing the key grind
+/// different seeds.
+const MIN_SHARED_SALT_BITS: f64 = 32.0;
+
+fn derive(tag: &[u8], master_seed: &[u8; 32]) -> [u8; 32] {
+    Sha256::new().chain_update(tag).chain_update(master_seed).finalize().into()
+}
+
+/// The base keypair of every search driven by `master_seed`.
+pub(crate) fn base_keypair_from_master_seed(master_seed: &[u8; 32]) -> Result<Keypair> {
+    keypair_from_seed(&derive(BASE_KEY_TAG, master_seed))
+        .map_err(|e| anyhow!("Failed to derive base keypair: {}", e))
+}
+
+/// Characters the generated part of a seed is written in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SeedAlphabet {
+    /// Letters and digits.
+    #[default]
+    Alnum,
+    /// Lowercase hex digits, like the seeds of the TypeScript workers.
+    Hex,
+    Base58,
+}
+
+impl SeedAlphabet {
+    fn chars(self) -> &'static [u8] {
+        match self {
+            SeedAlphabet::Alnum => CHARSET,
+            SeedAlphabet::Hex => HEX_CHARS,
+            SeedAlphabet::Base58 => BASE58_ALPHABET,
+        }
+    }
+}
+
+impl FromStr for SeedAlphabet {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "alnum" => Ok(SeedAlphabet::Alnum),
+            "hex" => Ok(SeedAlphabet::Hex),
+            "base58" => Ok(SeedAlphabet::Base58),
+            _ => Err(anyhow!(
+                "Seed alphabet must be one of 'alnum', 'hex' or 'base58', got '{}'",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SeedAlphabet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SeedAlphabet::Alnum => write!(f, "alnum"),
+            SeedAlphabet::Hex => write!(f, "hex"),
+            SeedAlphabet::Base58 => write!(f, "base58"),
+        }
+    }
+}
+
+/// What generated seeds look like: `prefix` followed by `len - prefix.len()`
+/// characters of `alphabet`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeedFormat {
+    /// Seed length in bytes, at most `MAX_SEED_LEN`.
+    pub len: usize,
+    pub alphabet: SeedAlphabet,
+    /// Fixed printable ASCII start of every seed, e.g. "texaglo-".
+    pub prefix: String,
+}
+
+impl Default for SeedFormat {
+    fn default() -> Self {
+        Self {
+            len: MAX_SEED_LEN,
+            alphabet: SeedAlphabet::default(),
+            prefix: String::new(),
+        }
+    }
+}
+
+impl SeedFormat {
+    pub fn validate(&self) -> Result<()> {
+        if self.len > MAX_SEED_LEN {
+            return Err(anyhow!(
+                "Seed length {} exceeds the maximum of {}",
+                self.len,
+                MAX_SEED_LEN
+            ));
+        }
+        if !self.prefix.bytes().all(|b| b.is_ascii_graphic()) {
+            return Err(anyhow!("Seed prefix must be printable ASCII without spaces"));
+        }
+        if self.prefix.len() >= self.len {
+            return Err(anyhow!(
+                "Seed prefix '{}' leaves no room in a {} byte seed",
+                self.prefix,
+                self.len
+            ));
+        }
+        Ok(())
+    }
+
+    /// Digits of the attempt index after the salt. As many as writing any u64
+    /// takes, or whatever room the prefix leaves.
+    fn counter_digits(&self) -> usize {
+        let base = self.alphabet.chars().len() as u128;
+        let mut digits = 1;
+        while base.pow(digits) <= u64::MAX as u128 {
+            digits += 1;
+        }
+        (digits as usize).min(self.len - self.prefix.len())
+    }
+
+    fn salt_len(&self) -> usize {
+        self.len - self.prefix.len() - self.counter_digits()
+    }
+
+    /// Checks the salt keeps searches on the same base key apart. Without one,
+    /// every run on the key grinds the same seeds from index 0.
+    pub(crate) fn validate_shared_base(&self) -> Result<()> {
+        self.validate()?;
+        let salt_bits = self.salt_len() as f64 * (self.alphabet.chars().len() as f64).log2();
+        if salt_bits < MIN_SHARED_SALT_BITS {
+            return Err(anyhow!(
+                "A {} byte seed with prefix '{}' leaves {} salt characters, too few for searches on the same base key to grind different seeds. Use a longer seed or a shorter prefix",
+                self.len,
+                self.prefix,
+                self.salt_len()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Maps attempt indices to seeds: the format's prefix, a random per-search salt
+/// and the index written in the format's alphabet, so every index yields a distinct
+/// seed and workers can walk their share of the index space without generating
+/// random numbers.
+#[derive(Debug, Clone)]
+pub(crate) struct SeedSchedule {
+    /// Prefix and salt, the fixed part of every seed.
+    template: [u8; MAX_SEED_LEN],
+    len: usize,
+    salt_start: usize,
+    counter_start: usize,
+    alphabet: &'static [u8],
+}
+
+impl SeedSchedule {
+    pub(crate) fn random(format: &SeedFormat) -> Result<Self> {
+        let alphabet = format.alphabet.chars();
+        let mut rng = rand::thread_rng();
+        Self::with_salt(format, |_| alphabet[rng.gen_range(0..alphabet.len())])
+    }
+
+    pub(crate) fn from_master_seed(master_seed: &[u8; 32], format: &SeedFormat) -> Result<Self> {
+        let alphabet = format.alphabet.chars();
+        let digest = derive(SALT_TAG, master_seed);
+        Self::with_salt(format, |i| alphabet[digest[i] as usize % alphabet.len()])
+    }
+
+    /// Rebuilds a schedule from the salt of a saved search.
+    pub(crate) fn from_salt(format: &SeedFormat, salt: &str) -> Result<Self> {
+        format.validate()?;
+        if salt.len() != format.salt_len() {
+            return Err(anyhow!("Seed salt must be {} characters", format.salt_len()));
+        }
+        if !salt.bytes().all(|c| format.alphabet.chars().contains(&c)) {
+            return Err(anyhow!("Seed salt contains characters outside the seed alphabet"));
+        }
+        Self::with_salt(format, |i| salt.as_bytes()[i])
+    }
+
+    fn with_salt(format: &SeedFormat, mut salt: impl FnMut(usize) -> u8) -> Result<Self> {
+        format.validate()?;
+        let mut template = [0u8; MAX_SEED_LEN];
+        let prefix_len = format.prefix.len();
+        template[..prefix_len].copy_from_slice(format.prefix.as_bytes());
+        for i in 0..format.salt_len() {
+            template[prefix_len + i] = salt(i);
+        }
+
+        Ok(Self {
+            template,
+            len: format.len,
+            salt_start: prefix_len,
+            counter_start: prefix_len + format.salt_len(),
+            alphabet: format.alphabet.chars(),
+        })
+    }
+
+    pub(crate) fn salt(&self) -> &str {
+        std::str::from_utf8(&self.template[self.salt_start..self.counter_start]).expect("salt is ASCII")
+    }
+
+    /// Number of attempt indices the counter digits can write, `None` when every
+    /// u64 fits.
+    pub(crate) fn capacity(&self) -> Option<u64> {
+        (self.alphabet.len() as u64).checked_pow((self.len - self.counter_start) as u32)
+    }
+
+    pub(crate) fn cursor(&self, index: u64) -> SeedCursor {
+        let mut seed = self.template;
+        let mut digits = [0u8; MAX_SEED_LEN];
+        let mut rest = index;
+        for i in (self.counter_start..self.len).rev() {
+            digits[i] = (rest % self.alphabet.len() as u64) as u8;
+            seed[i] = self.alphabet[digits[i] as usize];
+            rest /= self.alphabet.len() as u64;
+        }
+
+        SeedCursor {
+            seed,
+            digits,
+            len: self.len,
+            counter_start: self.counter_start,
+            alphabet: self.alphabet,
+        }
+    }
+}
+
+/// The seed for one attempt index, advanced to the next index in place.
+pub(crate) struct SeedCursor {
+    seed: [u8; MAX_SEED_LEN],
+    digits: [u8; MAX_SEED_LEN],
+    len: usize,
+    counter_start: usize,
+    alphabet: &'static [u8],
+}
+
+impl SeedCursor {
+    pub(crate) fn seed(&self) -> &[u8] {
+        &self.seed[..self.len]
+    }
+
+    pub(crate) fn advance(&mut self) {
+        for i in (self.counter_start..self.len).rev() {
+            self.digits[i] += 1;
+            if (self.digits[i] as usize) < self.alphabet.len() {
+                self.seed[i] = self.alphabet[self.digits[i] as usize];
+                return;
+            }
+            self.digits[i] = 0;
+            self.seed[i] = self.alphabet[0];
+        }
+    }
+}
+
+impl fmt::Display for SeedCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Seeds only ever contain ASCII characters
+        f.write_str(std::str::from_utf8(self.seed()).expect("seed is ASCII"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_advance_matches_fresh_cursor() {
+        let schedule = SeedSchedule::random(&SeedFormat::default()).unwrap();
+        for start in [0, 61, 62 * 62 - 3, u64::MAX - 2000] {
+            let mut cursor = schedule.cursor(start);
+            for i in 0..1000 {
+                assert_eq!(cursor.to_string(), schedule.cursor(start + i).to_string());
+                cursor.advance();
+            }
+        }
+    }
+
+    #[test]
+    fn test_seed_format_layout() {
+        let format = SeedFormat {
+            len: 14,
+            alphabet: SeedAlphabet::Hex,
+            prefix: "texaglo-".to_string(),
+        };
+        let schedule = SeedSchedule::random(&format).unwrap();
+        assert_eq!(schedule.capacity(), Some(16u64.pow(6)));
+        assert_eq!(schedule.cursor(0xabc).to_string(), "texaglo-000abc");
+        assert_eq!(SeedSchedule::from_salt(&format, schedule.salt()).unwrap().cursor(7).to_string(), "texaglo-000007");
+
+        // Long enough for any index, the rest is salt
+        let format = SeedFormat {
+            len: 20,
+            alphabet: SeedAlphabet::Hex,
+            prefix: String::new(),
+        };
+        let schedule = SeedSchedule::random(&format).unwrap();
+        assert_eq!(schedule.capacity(), None);
+        assert_eq!(schedule.salt().len(), 4);
+        assert!(schedule.cursor(u64::MAX).to_string().ends_with("ffffffffffffffff"));
+
+        // Runs on a given base key need salt to tell them apart
+        let texaglo = |len| SeedFormat {
+            len,
+            alphabet: SeedAlphabet::Hex,
+            prefix: "texaglo-".to_string(),
+        };
+        assert!(texaglo(14).validate_shared_base().is_err());
+        assert!(texaglo(32).validate_shared_base().is_ok());
+        assert!(format.validate_shared_base().is_err());
+        assert!(SeedFormat::default().validate_shared_base().is_ok());
+
+        for invalid in [
+            SeedFormat { len: 33, ..SeedFormat::default() },
+            SeedFormat { len: 8, prefix: "texaglo-".to_string(), ..SeedFormat::default() },
+            SeedFormat { prefix: "tex aglo".to_string(), ..SeedFormat::default() },
+        ] {
+            assert!(invalid.validate().is_err());
+        }
+    }
+}
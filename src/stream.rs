@@ -0,0 +1,114 @@
+use anyhow::Result;
+use futures::{channel::mpsc::Receiver, task::AtomicWaker, Stream, StreamExt};
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use crate::{CancelHandle, ProgressEvent, SearchSummary, VanityAddressResult};
+
+/// What a `SearchStream` yields while its search runs.
+#[derive(Debug, Clone)]
+pub enum SearchEvent {
+    Progress(ProgressEvent),
+    Match(VanityAddressResult),
+    /// The search stopped before it had every match. Always the last event.
+    Stopped(SearchSummary),
+}
+
+/// The newest progress event the stream hasn't yielded yet. Each event replaces
+/// the one before, so a stream nobody polls holds one at most.
+#[derive(Default)]
+pub(crate) struct LatestProgress {
+    event: Mutex<Option<ProgressEvent>>,
+    waker: AtomicWaker,
+}
+
+impl LatestProgress {
+    pub(crate) fn set(&self, event: ProgressEvent) {
+        *self.event.lock().expect("progress lock poisoned") = Some(event);
+        self.waker.wake();
+    }
+
+    fn take(&self) -> Option<ProgressEvent> {
+        self.event.lock().expect("progress lock poisoned").take()
+    }
+}
+
+/// The events of a search grinding on its own thread, see `VanitySearcher::stream`.
+pub struct SearchStream {
+    progress: Arc<LatestProgress>,
+    /// Matches and the last event, sized to hold all of them.
+    events: Receiver<Result<SearchEvent>>,
+    /// What `events` yielded while progress was still to go out before it.
+    next: Option<Option<Result<SearchEvent>>>,
+    cancel_on_drop: Option<CancelOnDrop>,
+}
+
+impl SearchStream {
+    pub(crate) fn new(
+        progress: Arc<LatestProgress>,
+        events: Receiver<Result<SearchEvent>>,
+        cancel: CancelHandle,
+    ) -> Self {
+        Self {
+            progress,
+            events,
+            next: None,
+            cancel_on_drop: Some(CancelOnDrop::new(cancel)),
+        }
+    }
+}
+
+impl Stream for SearchStream {
+    type Item = Result<SearchEvent>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.next.is_none() {
+            if let Poll::Ready(event) = this.events.poll_next_unpin(cx) {
+                this.next = Some(event);
+            }
+        }
+
+        // Progress was reported before anything in `events`, the last event included
+        this.progress.waker.register(cx.waker());
+        if let Some(event) = this.progress.take() {
+            return Poll::Ready(Some(Ok(SearchEvent::Progress(event))));
+        }
+        match this.next.take() {
+            Some(None) => {
+                // The search thread is done, there is nothing left to cancel
+                if let Some(cancel_on_drop) = this.cancel_on_drop.take() {
+                    cancel_on_drop.disarm();
+                }
+                Poll::Ready(None)
+            }
+            Some(event) => Poll::Ready(event),
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// Cancels a search whose future or stream is dropped before the search ends.
+pub(crate) struct CancelOnDrop(Option<CancelHandle>);
+
+impl CancelOnDrop {
+    pub(crate) fn new(cancel: CancelHandle) -> Self {
+        Self(Some(cancel))
+    }
+
+    /// The search ended, dropping no longer cancels it.
+    pub(crate) fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        if let Some(cancel) = self.0.take() {
+            cancel.cancel();
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use crate::pattern::{CharClass, BASE58_ALPHABET};
+
+/// Longest suffix checked arithmetically: 58^10 still fits a u64.
+pub(crate) const MAX_SUFFIX_LEN: usize = 10;
+
+/// Cap on the residues precomputed for one suffix. Wider patterns are only checked
+/// after a full base58 encode.
+const MAX_TARGETS: usize = 4096;
+
+/// The last k base58 characters of an address are the digits of its value modulo
+/// 58^k, so an end pattern is the set of residues of every spelling it accepts.
+#[derive(Debug, Clone)]
+pub(crate) struct SuffixTargets {
+    modulus: u64,
+    /// Sorted residues of every address suffix the end part accepts.
+    residues: Vec<u64>,
+}
+
+impl SuffixTargets {
+    pub(crate) fn new(classes: &[CharClass]) -> Option<Self> {
+        // Leading wildcards of a suffix never rule an address out
+        let start = classes
+            .iter()
+            .position(|class| class.base58_count() < BASE58_ALPHABET.len())?;
+        let classes = &classes[start..];
+
+        let variants = classes
+            .iter()
+            .try_fold(1usize, |n, class| n.checked_mul(class.base58_count()))?;
+        if classes.len() > MAX_SUFFIX_LEN || variants > MAX_TARGETS {
+            return None;
+        }
+
+        let mut residues = vec![0u64];
+        let mut place = 1u64;
+        for class in classes.iter().rev() {
+            let digits: Vec<u64> = (0..BASE58_ALPHABET.len() as u64)
+                .filter(|&d| class.contains(BASE58_ALPHABET[d as usize]))
+                .collect();
+            residues = residues
+                .iter()
+                .flat_map(|&r| digits.iter().map(move |&d| r + d * place))
+                .collect();
+            place *= BASE58_ALPHABET.len() as u64;
+        }
+        residues.sort_unstable();
+
+        Some(Self {
+            modulus: place,
+            residues,
+        })
+    }
+
+    pub(crate) fn modulus(&self) -> u64 {
+        self.modulus
+    }
+
+    /// Takes the address residue modulo any multiple of `modulus`.
+    pub(crate) fn contains(&self, residue: u64) -> bool {
+        self.residues.binary_search(&(residue % self.modulus)).is_ok()
+    }
+}
+
+/// Reduces raw addresses modulo a fixed 58^k straight from the hash bytes.
+#[derive(Debug, Clone)]
+pub(crate) struct ResidueReducer {
+    /// 2^(64 * j) mod `modulus` for each little-endian u64 limb of the address.
+    limb_weights: [u64; 4],
+    modulus: u64,
+}
+
+impl ResidueReducer {
+    pub(crate) fn new(modulus: u64) -> Self {
+        let mut limb_weights = [0u64; 4];
+        let mut weight = 1u128 % modulus as u128;
+        for limb_weight in &mut limb_weights {
+            *limb_weight = weight as u64;
+            weight = (weight << 64) % modulus as u128;
+        }
+        Self {
+            limb_weights,
+            modulus,
+        }
+    }
+
+    pub(crate) fn residue(&self, address: &[u8; 32]) -> u64 {
+        // Each limb * weight is below 2^123, so the sum of four cannot overflow
+        let value: u128 = address
+            .rchunks_exact(8)
+            .zip(self.limb_weights)
+            .map(|(limb, weight)| {
+                let limb = u64::from_be_bytes(limb.try_into().expect("8-byte chunk"));
+                limb as u128 * weight as u128
+            })
+            .sum();
+        (value % self.modulus as u128) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base58;
+    use crate::pattern::{CompiledPattern, PatternSpec, Position};
+
+    #[test]
+    fn test_residue_agrees_with_encoded_suffix() {
+        let mut out = [0u8; base58::MAX_ENCODED_LEN];
+        for (pattern, case_insensitive) in [("x", true), ("Z", false), ("[1-3]?k", true)] {
+            let spec = PatternSpec::new(pattern, Position::End, case_insensitive);
+            let compiled = CompiledPattern::compile(&spec).unwrap();
+            let targets = SuffixTargets::new(&compiled.classes).unwrap();
+            let reducer = ResidueReducer::new(targets.modulus());
+
+            for _ in 0..20_000 {
+                let address = rand::random::<[u8; 32]>();
+                let encoded = base58::encode_32(&address, &mut out);
+                assert_eq!(targets.contains(reducer.residue(&address)), compiled.matches(encoded));
+            }
+        }
+    }
+}
@@ -93,6 +93,12 @@ export async function findVanityAddress(
 ): Promise<{ keypair: Keypair; seed: string; tokenAddress: PublicKey }> {
     console.log(`Searching for vanity address ${position === 'start' ? 'starting' : 'ending'} with '${pattern}'...`);
     console.log(`Case ${caseInsensitive ? 'insensitive' : 'sensitive'} search`);
+
+    // Start patterns grind mint keypairs, which need no base keypair
+    if (position === 'start') {
+        console.log('Using Rust keypair search for start pattern...');
+        return findMintKeypair(pattern, caseInsensitive);
+    }
     
     // Create token keypair if it doesn't exist
     const keypairPath = path.join(KEYS_DIR, 'token_keypair.json');
@@ -134,74 +140,59 @@ export async function findVanityAddress(
         });
     });
 
-    // Use appropriate method based on position
-    if (position === 'start') {
-        console.log('Using vanity CLI for start pattern...');
-        return findVanityAddressStart(pattern, basePubkey, caseInsensitive);
-    } else {
-        console.log('Using GPU-accelerated search for end pattern...');
-        return findVanityAddressEnd(pattern, basePubkey, caseInsensitive);
-    }
+    console.log('Using GPU-accelerated search for end pattern...');
+    return findVanityAddressEnd(pattern, basePubkey, caseInsensitive);
 }
 
-async function findVanityAddressStart(
+async function findMintKeypair(
     pattern: string,
-    basePubkey: string,
     caseInsensitive: boolean
 ): Promise<{ keypair: Keypair; seed: string; tokenAddress: PublicKey }> {
-    return new Promise((resolve, reject) => {
-        const args = [
-            'grind',
-            '--base',
-            basePubkey,
-            '--owner',
-            'TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb',
-            '--target',
-            pattern,
-            '--num-cpus',
-            os.cpus().length.toString()
-        ];
-
-        if (caseInsensitive) {
-            args.push('--case-insensitive');
-        }
+    // Run in a scratch directory so the mint keypair the Rust search saves to
+    // token_keys/token_keypair.json doesn't replace our base keypair
+    const workDir = fs.mkdtempSync(path.join(os.tmpdir(), 'token22-vanity-'));
+    const args = ['--pattern', pattern, '--position', 'start', '--mode', 'keypair'];
+    if (caseInsensitive) {
+        args.push('--case-insensitive');
+    }
 
-        console.log(`Running vanity CLI with ${os.cpus().length} CPU cores...`);
-        const process = spawn('vanity', args);
-        let output = '';
-        
-        process.stdout.on('data', (data: Buffer) => {
-            const text = data.toString();
-            output += text;
-            if (text.includes('attempts per second')) {
-                console.log(text.trim()); // Show progress
-            }
+    return new Promise((resolve, reject) => {
+        const rustProcess = spawn(path.join(__dirname, '../target/release/token22-vanity'), args, {
+            cwd: workDir,
+            env: { ...process.env, RUST_LOG: 'info' }
         });
-        
-        process.stderr.on('data', (data: Buffer) => {
+        let output = '';
+
+        rustProcess.stdout.on('data', (data: Buffer) => {
             output += data.toString();
         });
-        
-        process.on('close', (code: number) => {
-            if (code === 0) {
-                const seedMatch = output.match(/-> ([^\s]+) in/);
-                const addressMatch = output.match(/found target: ([^\s;]+);/);
-                
-                if (seedMatch && addressMatch) {
-                    const seed = seedMatch[1];
-                    const tokenAddress = new PublicKey(addressMatch[1]);
-                    
-                    const keypairPath = path.join(KEYS_DIR, 'token_keypair.json');
-                    const baseKeypairData = JSON.parse(fs.readFileSync(keypairPath, 'utf-8'));
-                    const keypair = Keypair.fromSecretKey(new Uint8Array(baseKeypairData));
-                    
-                    resolve({ keypair, seed, tokenAddress });
-                } else {
-                    reject(new Error('Could not parse vanity address from output'));
-                }
-            } else {
+
+        rustProcess.stderr.on('data', (data: Buffer) => {
+            process.stderr.write(data); // Show progress
+        });
+
+        rustProcess.on('close', (code: number) => {
+            const resultMatch = output.match(/RESULT_START\n([\s\S]*?)\nRESULT_END/);
+            if (code !== 0 || !resultMatch) {
                 reject(new Error(`Process exited with code ${code}: ${output}`));
+                return;
             }
+
+            const { token_address } = JSON.parse(resultMatch[1]);
+            const keypairPath = path.join(workDir, 'token_keys', 'token_keypair.json');
+            const keypair = Keypair.fromSecretKey(new Uint8Array(JSON.parse(fs.readFileSync(keypairPath, 'utf-8'))));
+            fs.rmSync(workDir, { recursive: true, force: true });
+            if (keypair.publicKey.toBase58() !== token_address) {
+                reject(new Error(`Mint keypair ${keypair.publicKey.toBase58()} does not match ${token_address}`));
+                return;
+            }
+
+            // The mint keypair is the token address itself, there is no seed
+            resolve({ keypair, seed: '', tokenAddress: keypair.publicKey });
+        });
+
+        rustProcess.on('error', (error: Error) => {
+            reject(new Error(`Failed to start Rust process: ${error.message}`));
         });
     });
 }
@@ -389,27 +380,46 @@ export async function createToken(
     // Save keypair
     saveTokenKeypair(keypair, config.name);
     
+    // A start pattern keypair is the mint itself, which can't pay fees once it is
+    // created, so the payer creates it and pays for every transaction
+    const signers = config.position === 'start' ? [payer, keypair] : [keypair];
+
     // Fund the keypair
-    await fundAccount(connection, payer, keypair.publicKey, 0.1);
+    if (config.position === 'end') {
+        await fundAccount(connection, payer, keypair.publicKey, 0.1);
+    }
     
     // Calculate space needed for mint with metadata
     const mintLen = getMintLen([ExtensionType.MetadataPointer]);
+    const lamports = await connection.getMinimumBalanceForRentExemption(mintLen);
     
     // Create transaction
     const transaction = new Transaction();
     
     // Create token account
-    transaction.add(
-        SystemProgram.createAccountWithSeed({
-            fromPubkey: keypair.publicKey,
-            newAccountPubkey: tokenAddress,
-            basePubkey: keypair.publicKey,
-            seed: seed,
-            lamports: await connection.getMinimumBalanceForRentExemption(mintLen),
-            space: mintLen,
-            programId: TOKEN_2022_PROGRAM_ID
-        })
-    );
+    if (config.position === 'end') {
+        transaction.add(
+            SystemProgram.createAccountWithSeed({
+                fromPubkey: keypair.publicKey,
+                newAccountPubkey: tokenAddress,
+                basePubkey: keypair.publicKey,
+                seed: seed,
+                lamports,
+                space: mintLen,
+                programId: TOKEN_2022_PROGRAM_ID
+            })
+        );
+    } else {
+        transaction.add(
+            SystemProgram.createAccount({
+                fromPubkey: payer.publicKey,
+                newAccountPubkey: tokenAddress,
+                lamports,
+                space: mintLen,
+                programId: TOKEN_2022_PROGRAM_ID
+            })
+        );
+    }
     
     // Initialize metadata pointer
     transaction.add(
@@ -433,7 +443,7 @@ export async function createToken(
     );
 
     // Send transaction
-    const signature = await connection.sendTransaction(transaction, [keypair]);
+    const signature = await connection.sendTransaction(transaction, signers);
     await connection.confirmTransaction(signature);
     
     // Save seed information
@@ -456,7 +466,7 @@ export async function createToken(
             TOKEN_2022_PROGRAM_ID
         )
     );
-    const mintAuthSignature = await connection.sendTransaction(mintAuthTransaction, [keypair]);
+    const mintAuthSignature = await connection.sendTransaction(mintAuthTransaction, signers);
     await connection.confirmTransaction(mintAuthSignature);
 
     // Transfer freeze authority to admin wallet
@@ -470,7 +480,7 @@ export async function createToken(
             TOKEN_2022_PROGRAM_ID
         )
     );
-    const freezeAuthSignature = await connection.sendTransaction(freezeAuthTransaction, [keypair]);
+    const freezeAuthSignature = await connection.sendTransaction(freezeAuthTransaction, signers);
     await connection.confirmTransaction(freezeAuthSignature);
 
     // Verify authorities